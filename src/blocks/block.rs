@@ -1,137 +1,151 @@
-use crate::components::{
-    cell_size::CellSize, directions::Direction, grid_position::GridPosition, pixel::Pixel,
-};
+use crate::components::{cell::Cell, directions::Direction, grid_position::GridPosition};
+use crate::simulation::context::UpdateContext;
 
-use ggez::graphics;
-use std::collections::HashMap;
-use strum::IntoEnumIterator;
+use ggez::glam::Vec2;
+use rand::Rng;
 
-pub trait Block {
-    fn new(position: GridPosition) -> Self
-    where
-        Self: Sized;
-    fn get_pixel(&self) -> &Pixel;
-    fn set_position(&mut self, position: GridPosition);
-    fn get_position(&self) -> GridPosition;
-    fn box_clone(&self) -> Box<dyn Block>;
-    fn is_falling(&self) -> bool;
+/// Added to a falling cell's downward velocity every tick
+pub const GRAVITY: f32 = 0.25;
 
-    fn apply_motion(
-        &mut self,
-        ctx: &mut ggez::Context,
-        blocks: &HashMap<GridPosition, Box<dyn Block>>,
-        cell_size: CellSize,
-    );
+/// Fastest a cell can travel, in cells per tick
+pub const MAX_SPEED: f32 = 8.0;
 
-    fn apply_gravity(
-        &mut self,
-        ctx: &mut ggez::Context,
-        blocks: &HashMap<GridPosition, Box<dyn Block>>,
-        cell_size: CellSize,
-    );
-
-    fn append_to_mesh(&self, mesh_builder: &mut graphics::MeshBuilder) {
-        self.get_pixel().append_to_mesh(mesh_builder);
-    }
+/// Share of downward velocity turned sideways when a cell lands
+pub const IMPACT_TRANSFER: f32 = 0.4;
 
-    fn should_apply_gravity(
-        &self,
-        ctx: &mut ggez::Context,
-        blocks: &HashMap<GridPosition, Box<dyn Block>>,
-        cell_size: CellSize,
-    ) -> Option<GridPosition> {
-        let position = self.get_position();
-        let next_position = position + GridPosition::new(0, 1, cell_size);
-
-        let is_occupied = blocks.contains_key(&next_position);
-        let is_offscreen = next_position.is_offscreen(ctx);
-
-        if is_occupied || is_offscreen {
-            return None;
-        }
+/// Sideways velocity kept each tick
+pub const SIDEWAYS_DAMPING: f32 = 0.7;
 
-        Some(next_position)
-    }
+/// Sideways speed below which a cell comes to rest
+const REST_SPEED: f32 = 0.1;
 
-    fn get_open_directions(
+/// How cells of a phase move. Implementors hold no data, per-cell state
+/// lives in `Cell` and per-material properties in the `MaterialRegistry`.
+pub trait Block {
+    /// Accelerates the cell and moves it along its velocity, returning
+    /// where it ends up. Landing turns part of the fall into sideways
+    /// motion so grains scatter on impact, and cells thrown upwards slow
+    /// down until they fall again.
+    fn apply_gravity(
         &self,
-        blocks: &HashMap<GridPosition, Box<dyn Block>>,
-        cell_size: CellSize,
-    ) -> Vec<Direction> {
-        let position = self.get_position();
-        let mut open_directions = Vec::new();
-
-        let bottom_left = position + Direction::BottomLeft.get_offset(cell_size);
-        let bottom_right = position + Direction::BottomRight.get_offset(cell_size);
-        let bottom = position + Direction::Bottom.get_offset(cell_size);
-
-        if !blocks.contains_key(&bottom_left) {
-            open_directions.push(Direction::BottomLeft);
+        cell: &mut Cell,
+        position: GridPosition,
+        ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        let can_fall = self.can_move_into(cell, position + Direction::Bottom.get_offset(), ctx);
+
+        if cell.velocity.y < 0.0 {
+            cell.velocity.y += GRAVITY;
+        } else if can_fall {
+            cell.velocity.y = (cell.velocity.y + GRAVITY).clamp(1.0, MAX_SPEED);
+        } else if cell.velocity.y > 1.0 {
+            let direction = if ctx.rng.gen::<bool>() { 1.0 } else { -1.0 };
+
+            cell.velocity.x += direction * cell.velocity.y * IMPACT_TRANSFER;
+            cell.velocity.y = 0.0;
+        } else {
+            cell.velocity.y = 0.0;
         }
 
-        if !blocks.contains_key(&bottom_right) {
-            open_directions.push(Direction::BottomRight);
+        cell.velocity = cell
+            .velocity
+            .clamp(Vec2::splat(-MAX_SPEED), Vec2::splat(MAX_SPEED));
+
+        let travel = GridPosition::new(
+            cell.velocity.x.round() as i32,
+            cell.velocity.y.round() as i32,
+        );
+
+        let (next_position, blocked) = self.trace_path(cell, position, travel, ctx);
+
+        if blocked {
+            cell.velocity.x = 0.0;
+            cell.velocity.y = cell.velocity.y.max(0.0);
         }
 
-        if !blocks.contains_key(&bottom) {
-            open_directions.push(Direction::Bottom);
+        cell.velocity.x *= SIDEWAYS_DAMPING;
+
+        if cell.velocity.x.abs() < REST_SPEED {
+            cell.velocity.x = 0.0;
         }
 
-        open_directions
+        // Rising counts too, the cell is in the air either way
+        cell.falling = next_position.y != position.y;
+
+        next_position
     }
 
-    fn get_surrounding_blocks<'a>(
+    /// Returns the position the cell ends up at after sliding
+    fn apply_motion(
         &self,
-        blocks: &'a HashMap<GridPosition, Box<dyn Block>>,
-        cell_size: CellSize,
-    ) -> HashMap<Direction, Option<&'a Box<dyn Block>>> {
-        let position = self.get_position();
-        let mut surrounding_blocks = HashMap::new();
-
-        for direction in Direction::iter() {
-            let offset = direction.get_offset(cell_size);
-            let position = position + offset;
+        cell: &mut Cell,
+        position: GridPosition,
+        ctx: &mut UpdateContext,
+    ) -> GridPosition;
+
+    /// Steps cell by cell along a straight line, stopping at the last
+    /// position the block can move into. Entering a fluid also ends the
+    /// trip, so blocks sink through liquids one cell at a time, and a fluid
+    /// further along waits until the block is next to it. Returns the end
+    /// position and whether an obstacle cut the trip short.
+    fn trace_path(
+        &self,
+        cell: &Cell,
+        position: GridPosition,
+        travel: GridPosition,
+        ctx: &UpdateContext,
+    ) -> (GridPosition, bool) {
+        let steps = travel.x.abs().max(travel.y.abs());
+        let mut last_position = position;
+
+        for step in 1..=steps {
+            let offset = GridPosition::new(
+                (travel.x as f32 * step as f32 / steps as f32).round() as i32,
+                (travel.y as f32 * step as f32 / steps as f32).round() as i32,
+            );
+            let next_position = position + offset;
+
+            if !self.can_move_into(cell, next_position, ctx) {
+                let blocked = !self.is_open(cell, next_position, ctx);
+
+                return (last_position, blocked);
+            }
 
-            let block = blocks.get(&position);
+            last_position = next_position;
 
-            surrounding_blocks.insert(direction, block);
+            if ctx.material_at(next_position).is_some() {
+                break;
+            }
         }
 
-        surrounding_blocks
+        (last_position, false)
     }
 
-    /// Returns blocks if they exist in the given directions
-    fn get_blocks_from_directions<'a>(
-        &self,
-        blocks: &'a HashMap<GridPosition, Box<dyn Block>>,
-        cell_size: CellSize,
-        directions: Vec<Direction>,
-    ) -> HashMap<Direction, &'a Box<dyn Block>> {
-        let mut direction_blocks = HashMap::new();
-        let position = self.get_position();
-
-        for direction in directions {
-            let offset = direction.get_offset(cell_size);
-            let position = position + offset;
-
-            let block = blocks.get(&position);
-
-            match block {
-                Some(block) => {
-                    direction_blocks.insert(direction, block);
-                }
-                None => continue,
-            }
+    /// Whether this block can move to the position. Fluids are only swapped
+    /// with when they are right next to where the cell started the tick, so
+    /// a fast cell never throws one back across its path.
+    fn can_move_into(&self, cell: &Cell, position: GridPosition, ctx: &UpdateContext) -> bool {
+        if !self.is_open(cell, position, ctx) {
+            return false;
         }
 
-        direction_blocks
+        ctx.origin.distance(position) <= 1
+            || ctx.grid.get(position).is_some_and(|other| other.is_empty())
     }
 
-    fn offset_bottom_left(&mut self, cell_size: CellSize) -> GridPosition {
-        self.get_position() + Direction::BottomLeft.get_offset(cell_size)
-    }
+    /// Whether the position is empty or holds a lighter fluid the block
+    /// could swap with.
+    fn is_open(&self, cell: &Cell, position: GridPosition, ctx: &UpdateContext) -> bool {
+        let other = match ctx.grid.get(position) {
+            Some(other) => other,
+            None => return false,
+        };
+
+        let (material, other) = match (ctx.material(cell), ctx.material(&other)) {
+            (Some(material), Some(other)) => (material, other),
+            _ => return other.is_empty(),
+        };
 
-    fn offset_bottom_right(&mut self, cell_size: CellSize) -> GridPosition {
-        self.get_position() + Direction::BottomRight.get_offset(cell_size)
+        other.phase.is_fluid() && material.density > other.density
     }
 }
@@ -0,0 +1,60 @@
+use rand::Rng;
+
+use super::block::Block;
+use crate::components::{cell::Cell, directions::Direction, grid_position::GridPosition};
+use crate::simulation::context::UpdateContext;
+
+/// Chance each tick that a flame drifts upwards instead of staying put
+const RISE_CHANCE: f64 = 0.5;
+
+/// Flickers through its palette while drifting upwards, like fire. Flames
+/// cling to anything flammable they touch so fires keep burning in place.
+#[derive(Debug, Clone, Copy)]
+pub struct Fire;
+
+impl Block for Fire {
+    fn apply_gravity(
+        &self,
+        _cell: &mut Cell,
+        position: GridPosition,
+        _ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        position
+    }
+
+    fn apply_motion(
+        &self,
+        cell: &mut Cell,
+        position: GridPosition,
+        ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        let shades = ctx
+            .material(cell)
+            .map_or(1, |material| material.colors.len());
+        cell.shade = ctx.rng.gen_range(0..shades) as u8;
+
+        let touching_fuel = ctx.touching(position, |material| material.flammability > 0.0);
+
+        if touching_fuel || !ctx.rng.gen_bool(RISE_CHANCE) {
+            return position;
+        }
+
+        let direction = match ctx.rng.gen_range(0..3) {
+            0 => Direction::TopLeft,
+            1 => Direction::Top,
+            _ => Direction::TopRight,
+        };
+
+        let next_position = position + direction.get_offset();
+
+        if ctx
+            .grid
+            .get(next_position)
+            .is_some_and(|other| other.is_empty())
+        {
+            next_position
+        } else {
+            position
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use rand::Rng;
+
+use super::block::Block;
+use crate::components::{cell::Cell, directions::Direction, grid_position::GridPosition};
+use crate::simulation::context::UpdateContext;
+
+/// Chance each tick that a gas drifts sideways before trying to rise
+const DIFFUSION_CHANCE: f64 = 0.3;
+
+/// Rises and drifts about at random, like smoke. Anything denser pushes
+/// it aside, and a lighter gas bubbles up through a heavier one.
+#[derive(Debug, Clone, Copy)]
+pub struct Gas;
+
+impl Gas {
+    /// Whether the gas can rise into the position, either because it is
+    /// empty or because it holds a heavier gas the two can swap with.
+    fn can_rise_into(&self, cell: &Cell, position: GridPosition, ctx: &UpdateContext) -> bool {
+        let other = match ctx.grid.get(position) {
+            Some(other) => other,
+            None => return false,
+        };
+
+        match (ctx.material(cell), ctx.material(&other)) {
+            (Some(material), Some(other)) => {
+                other.phase == material.phase && other.density > material.density
+            }
+            _ => other.is_empty(),
+        }
+    }
+}
+
+impl Block for Gas {
+    fn apply_gravity(
+        &self,
+        _cell: &mut Cell,
+        position: GridPosition,
+        _ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        position
+    }
+
+    fn apply_motion(
+        &self,
+        cell: &mut Cell,
+        position: GridPosition,
+        ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        let (diagonals, sides) = if ctx.rng.gen::<bool>() {
+            (
+                [Direction::TopLeft, Direction::TopRight],
+                [Direction::Left, Direction::Right],
+            )
+        } else {
+            (
+                [Direction::TopRight, Direction::TopLeft],
+                [Direction::Right, Direction::Left],
+            )
+        };
+
+        if ctx.rng.gen_bool(DIFFUSION_CHANCE) {
+            for direction in sides {
+                let next_position = position + direction.get_offset();
+
+                if ctx
+                    .grid
+                    .get(next_position)
+                    .is_some_and(|other| other.is_empty())
+                {
+                    return next_position;
+                }
+            }
+        }
+
+        for direction in [Direction::Top, diagonals[0], diagonals[1]] {
+            let next_position = position + direction.get_offset();
+
+            if self.can_rise_into(cell, next_position, ctx) {
+                return next_position;
+            }
+        }
+
+        position
+    }
+}
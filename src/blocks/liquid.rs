@@ -0,0 +1,96 @@
+use rand::Rng;
+
+use super::block::Block;
+use crate::components::{cell::Cell, directions::Direction, grid_position::GridPosition};
+use crate::simulation::context::UpdateContext;
+
+/// Falls like a powder, then spreads sideways until it levels out, like
+/// water. How far it spreads per tick is the material's `dispersion`, and
+/// how often it holds still instead is its `viscosity`.
+#[derive(Debug, Clone, Copy)]
+pub struct Liquid;
+
+impl Liquid {
+    /// Where the liquid flows to in the given direction, up to
+    /// `dispersion` cells towards the nearest drop it can see. It looks as
+    /// far as the reach left after what the cell already travelled this
+    /// tick, and stays put when there is no drop, so pools settle flat
+    /// instead of sloshing about forever.
+    fn flow_target(
+        &self,
+        cell: &Cell,
+        position: GridPosition,
+        ctx: &UpdateContext,
+        direction: Direction,
+    ) -> Option<GridPosition> {
+        let dispersion = ctx.material(cell).map_or(0, |material| material.dispersion);
+        let offset = direction.get_offset();
+        let below = Direction::Bottom.get_offset();
+        let mut next_position = position;
+
+        for distance in 1..=ctx.reach_left(position + below) {
+            next_position += offset;
+
+            if !self.can_move_into(cell, next_position, ctx) {
+                return None;
+            }
+
+            if self.can_move_into(cell, next_position + below, ctx) {
+                let steps = distance.min(dispersion);
+
+                return (steps > 0).then(|| position + GridPosition::new(offset.x * steps, 0));
+            }
+        }
+
+        None
+    }
+}
+
+impl Block for Liquid {
+    fn apply_motion(
+        &self,
+        cell: &mut Cell,
+        position: GridPosition,
+        ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        if cell.falling {
+            return position;
+        }
+
+        let (diagonals, sides) = if ctx.rng.gen::<bool>() {
+            (
+                [Direction::BottomLeft, Direction::BottomRight],
+                [Direction::Left, Direction::Right],
+            )
+        } else {
+            (
+                [Direction::BottomRight, Direction::BottomLeft],
+                [Direction::Right, Direction::Left],
+            )
+        };
+
+        for direction in diagonals {
+            let next_position = position + direction.get_offset();
+
+            if self.can_move_into(cell, next_position, ctx) {
+                return next_position;
+            }
+        }
+
+        let viscosity = ctx
+            .material(cell)
+            .map_or(0.0, |material| material.viscosity);
+
+        if ctx.rng.gen::<f32>() < viscosity {
+            return position;
+        }
+
+        for direction in sides {
+            if let Some(next_position) = self.flow_target(cell, position, ctx, direction) {
+                return next_position;
+            }
+        }
+
+        position
+    }
+}
@@ -0,0 +1,110 @@
+use rand::Rng;
+
+use super::block::Block;
+use crate::components::{cell::Cell, directions::Direction, grid_position::GridPosition};
+use crate::simulation::context::UpdateContext;
+
+/// Falls and slides off piles, like sand. How steep a pile stands is set by
+/// the material's `slope`, and how readily a grain at rest starts sliding by
+/// its `friction`.
+#[derive(Debug, Clone, Copy)]
+pub struct Powder;
+
+impl Powder {
+    /// Cells a pile has to drop between the column and the one to its
+    /// right. Fractions of `slope` are carried over from column to column,
+    /// so over a whole pile the drops average out to the angle of repose.
+    fn drop_after(&self, cell: &Cell, column: i32, ctx: &UpdateContext) -> i32 {
+        let slope = ctx.material(cell).map_or(1.0, |material| material.slope);
+
+        ((slope * (column + 1) as f32).floor() - (slope * column as f32).floor()) as i32
+    }
+
+    /// Where the grain slides to on the given side, if anywhere. Steep
+    /// materials need the neighbouring column to drop away by a few cells,
+    /// shallow ones spread across several columns for every cell they drop.
+    fn slide_target(
+        &self,
+        cell: &Cell,
+        position: GridPosition,
+        side: i32,
+        ctx: &UpdateContext,
+    ) -> Option<GridPosition> {
+        let reach = ctx.reach_left(position);
+
+        for run in 1..=reach {
+            let column = position.x + side * run;
+            let mut drop = self.drop_after(cell, column.min(column - side), ctx);
+
+            // Materials too shallow to drop within reach drop at its end
+            if run == reach {
+                drop = drop.max(1);
+            }
+
+            if drop == 0 {
+                if !self.is_open(cell, GridPosition::new(column, position.y), ctx) {
+                    return None;
+                }
+
+                continue;
+            }
+
+            let target = GridPosition::new(column, position.y + 1);
+
+            let open = self.can_move_into(cell, target, ctx)
+                && (1..drop.min(reach))
+                    .all(|depth| self.is_open(cell, target + GridPosition::new(0, depth), ctx));
+
+            return open.then_some(target);
+        }
+
+        None
+    }
+}
+
+impl Block for Powder {
+    fn apply_motion(
+        &self,
+        cell: &mut Cell,
+        position: GridPosition,
+        ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        if cell.falling {
+            return position;
+        }
+
+        let bottom_cell = match ctx.grid.get(position + Direction::Bottom.get_offset()) {
+            Some(bottom_cell) if !bottom_cell.is_empty() => bottom_cell,
+            _ => return position,
+        };
+
+        if bottom_cell.falling {
+            return position;
+        }
+
+        // Grains still carrying sideways speed keep going, only ones at rest
+        // have to overcome friction
+        let friction = ctx.material(cell).map_or(0.0, |material| material.friction);
+
+        if cell.velocity.x == 0.0 && ctx.rng.gen::<f32>() < friction {
+            return position;
+        }
+
+        let left = self.slide_target(cell, position, -1, ctx);
+        let right = self.slide_target(cell, position, 1, ctx);
+
+        match (left, right) {
+            (Some(left), Some(right)) => {
+                if ctx.rng.gen::<bool>() {
+                    left
+                } else {
+                    right
+                }
+            }
+            (Some(left), None) => left,
+            (None, Some(right)) => right,
+            // No open directions
+            (None, None) => position,
+        }
+    }
+}
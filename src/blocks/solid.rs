@@ -0,0 +1,27 @@
+use super::block::Block;
+use crate::components::{cell::Cell, grid_position::GridPosition};
+use crate::simulation::context::UpdateContext;
+
+/// Never moves on its own, like stone.
+#[derive(Debug, Clone, Copy)]
+pub struct Solid;
+
+impl Block for Solid {
+    fn apply_gravity(
+        &self,
+        _cell: &mut Cell,
+        position: GridPosition,
+        _ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        position
+    }
+
+    fn apply_motion(
+        &self,
+        _cell: &mut Cell,
+        position: GridPosition,
+        _ctx: &mut UpdateContext,
+    ) -> GridPosition {
+        position
+    }
+}
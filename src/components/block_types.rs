@@ -1,6 +1,18 @@
+use super::material::MaterialId;
+
+/// What the mouse places or removes
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum BlockType {
-    Sand,
-    Stone,
+    Material(MaterialId),
     Eraser,
 }
+
+impl BlockType {
+    /// The material placed by this tool, `None` for tools that remove blocks.
+    pub fn material(&self) -> Option<MaterialId> {
+        match self {
+            BlockType::Material(material) => Some(*material),
+            BlockType::Eraser => None,
+        }
+    }
+}
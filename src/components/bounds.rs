@@ -0,0 +1,26 @@
+use super::{cell_size::CellSize, grid_position::GridPosition};
+
+/// Size of the simulated area, in cells
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Bounds {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Bounds {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+
+    /// Number of whole cells that fit inside the given pixel size.
+    pub fn from_pixels(width: f32, height: f32, cell_size: CellSize) -> Self {
+        let width = (width / cell_size.width) as i32;
+        let height = (height / cell_size.height) as i32;
+
+        Self::new(width, height)
+    }
+
+    pub fn contains(&self, position: GridPosition) -> bool {
+        position.x >= 0 && position.y >= 0 && position.x < self.width && position.y < self.height
+    }
+}
@@ -0,0 +1,63 @@
+use ggez::glam::Vec2;
+
+use super::material::MaterialId;
+use crate::simulation::heat::AMBIENT_TEMPERATURE;
+
+/// A single grid cell, kept small so the whole grid stays contiguous.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cell {
+    pub material: MaterialId,
+    /// Index into the material's colour palette
+    pub shade: u8,
+    pub falling: bool,
+    /// Cells travelled per tick
+    pub velocity: Vec2,
+    /// In degrees Celsius
+    pub temperature: f32,
+    /// Ticks left before the cell decays, unused by materials that last
+    pub lifetime: u16,
+    /// Integrity left, worn down by acid until the cell dissolves at zero
+    pub health: u8,
+    /// Liquid soaked up, counts down to zero as the cell dries out
+    pub moisture: u8,
+    /// Cells dissolved into this one, like salt in salt water
+    pub concentration: u8,
+    /// Last tick this cell was updated on, zero until its first update.
+    /// Counts every tick so a stamp never comes round again, even for a
+    /// cell that slept for a long time.
+    pub clock: u64,
+}
+
+impl Cell {
+    pub const EMPTY: Cell = Cell {
+        material: MaterialId::EMPTY,
+        shade: 0,
+        falling: false,
+        velocity: Vec2::ZERO,
+        temperature: AMBIENT_TEMPERATURE,
+        lifetime: 0,
+        health: u8::MAX,
+        moisture: 0,
+        concentration: 0,
+        clock: 0,
+    };
+
+    pub fn new(material: MaterialId, shade: u8, temperature: f32) -> Self {
+        Self {
+            material,
+            shade,
+            falling: false,
+            velocity: Vec2::ZERO,
+            temperature,
+            lifetime: 0,
+            health: u8::MAX,
+            moisture: 0,
+            concentration: 0,
+            clock: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.material.is_empty()
+    }
+}
@@ -1,4 +1,4 @@
-use super::{cell_size::CellSize, grid_position::GridPosition};
+use super::grid_position::GridPosition;
 use strum_macros::EnumIter;
 
 /// [TopLeft]    | [Top]    | [TopRight]
@@ -6,7 +6,7 @@ use strum_macros::EnumIter;
 /// [Left]       | [Middle] | [Right]
 ///
 /// [BottomLeft] | [Bottom] | [BottomRight]
-#[derive(Eq, Hash, PartialEq, EnumIter)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq, EnumIter)]
 pub enum Direction {
     TopLeft,
     Top,
@@ -22,19 +22,19 @@ pub enum Direction {
 }
 
 impl Direction {
-    pub fn get_offset(&self, cell_size: CellSize) -> GridPosition {
+    pub fn get_offset(&self) -> GridPosition {
         match self {
-            Direction::TopLeft => GridPosition::new(-1, -1, cell_size),
-            Direction::Top => GridPosition::new(0, -1, cell_size),
-            Direction::TopRight => GridPosition::new(1, -1, cell_size),
+            Direction::TopLeft => GridPosition::new(-1, -1),
+            Direction::Top => GridPosition::new(0, -1),
+            Direction::TopRight => GridPosition::new(1, -1),
 
-            Direction::Left => GridPosition::new(-1, 0, cell_size),
+            Direction::Left => GridPosition::new(-1, 0),
             Direction::Middle => panic!("Offset within itself"), // Should never happen
-            Direction::Right => GridPosition::new(1, 0, cell_size),
+            Direction::Right => GridPosition::new(1, 0),
 
-            Direction::BottomLeft => GridPosition::new(-1, 1, cell_size),
-            Direction::Bottom => GridPosition::new(0, 1, cell_size),
-            Direction::BottomRight => GridPosition::new(1, 1, cell_size),
+            Direction::BottomLeft => GridPosition::new(-1, 1),
+            Direction::Bottom => GridPosition::new(0, 1),
+            Direction::BottomRight => GridPosition::new(1, 1),
         }
     }
 }
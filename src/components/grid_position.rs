@@ -1,33 +1,21 @@
-use std::{
-    collections::HashMap,
-    hash::{Hash, Hasher},
-    ops::{Add, AddAssign, Mul},
-};
+use std::ops::{Add, AddAssign, Mul};
 
 use ggez::glam::*;
 use ggez::graphics;
 
-use crate::blocks::block::Block;
-
 use super::cell_size::CellSize;
 
-// pub fn round_to_multiple(number: i32, cell_size: CellSize) -> i32 {
-//     (number / cell_size).round() * cell_size
-// }
-
-#[derive(Debug, Clone, Copy)]
+/// Column and row of a cell. Conversions to and from screen space take
+/// the `CellSize` explicitly so positions stay valid when it changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GridPosition {
-    pub cell_size: CellSize,
     pub x: i32,
     pub y: i32,
 }
 
 impl GridPosition {
-    pub fn new(x: i32, y: i32, cell_size: CellSize) -> Self {
-        let x = x * cell_size.width as i32;
-        let y = y * cell_size.height as i32;
-
-        Self { cell_size, x, y }
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
     }
 
     /// Finds the closest grid position to the given point.
@@ -37,46 +25,29 @@ impl GridPosition {
     {
         let point: Vec2 = point.into();
 
-        let x = (point.x / cell_size.width as f32).round() as i32;
-        let y = (point.y / cell_size.height as f32).round() as i32;
+        let x = (point.x / cell_size.width).round() as i32;
+        let y = (point.y / cell_size.height).round() as i32;
 
-        Self::new(x, y, cell_size)
+        Self::new(x, y)
     }
 
-    pub fn as_vec2(&self) -> Vec2 {
-        Vec2::new(self.x as f32, self.y as f32)
+    /// Steps between two positions when diagonal steps count as one
+    pub fn distance(&self, other: GridPosition) -> i32 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
     }
 
-    pub fn as_rect(&self) -> graphics::Rect {
-        graphics::Rect::new(
-            self.x as f32,
-            self.y as f32,
-            self.cell_size.width,
-            self.cell_size.height,
+    /// Top left corner of the cell in screen space.
+    pub fn as_vec2(&self, cell_size: CellSize) -> Vec2 {
+        Vec2::new(
+            self.x as f32 * cell_size.width,
+            self.y as f32 * cell_size.height,
         )
     }
 
-    pub fn is_occupied(&self, blocks: &HashMap<GridPosition, Box<dyn Block>>) -> bool {
-        blocks.contains_key(self)
-    }
-
-    pub fn is_offscreen(&self, ctx: &mut ggez::Context) -> bool {
-        let window_size = ctx.gfx.drawable_size();
-
-        let top = 0.0;
-        let left = 0.0;
-        let bottom = window_size.1 - self.cell_size.height;
-        let right = window_size.0 - self.cell_size.width;
-
-        let position = self.as_vec2();
+    pub fn as_rect(&self, cell_size: CellSize) -> graphics::Rect {
+        let position = self.as_vec2(cell_size);
 
-        return position.x < left || position.x > right || position.y < top || position.y > bottom;
-    }
-}
-
-impl PartialEq for GridPosition {
-    fn eq(&self, other: &Self) -> bool {
-        self.cell_size == other.cell_size && self.x == other.x && self.y == other.y
+        graphics::Rect::new(position.x, position.y, cell_size.width, cell_size.height)
     }
 }
 
@@ -92,7 +63,6 @@ impl Add for GridPosition {
 
     fn add(self, other: Self) -> Self {
         Self {
-            cell_size: self.cell_size,
             x: self.x + other.x,
             y: self.y + other.y,
         }
@@ -104,20 +74,8 @@ impl Mul for GridPosition {
 
     fn mul(self, other: Self) -> Self {
         Self {
-            cell_size: self.cell_size,
             x: self.x * other.x,
             y: self.y * other.y,
         }
     }
 }
-
-impl Eq for GridPosition {}
-
-impl Hash for GridPosition {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.cell_size.width.to_bits().hash(state);
-        self.cell_size.height.to_bits().hash(state);
-        self.x.hash(state);
-        self.y.hash(state);
-    }
-}
@@ -0,0 +1,150 @@
+use ggez::graphics::Color;
+
+use super::phase::Phase;
+use crate::blocks::{
+    block::Block, fire::Fire, gas::Gas, liquid::Liquid, powder::Powder, solid::Solid,
+};
+
+/// Identifies what a cell is made of, an index into the `MaterialRegistry`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MaterialId(pub u16);
+
+impl MaterialId {
+    pub const EMPTY: MaterialId = MaterialId(0);
+
+    pub fn is_empty(&self) -> bool {
+        *self == MaterialId::EMPTY
+    }
+}
+
+/// A change of material once a temperature is passed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub temperature: f32,
+    pub into: MaterialId,
+}
+
+/// Packing into another material under the weight of what is piled on top
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compaction {
+    /// Cells that need to be stacked directly above
+    pub depth: i32,
+    pub into: MaterialId,
+}
+
+/// Soaking up a neighbouring liquid, like sand turning into wet sand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Absorption {
+    pub liquid: MaterialId,
+    /// What the cell turns into once it has soaked up a cell of the liquid,
+    /// a cell already of this material stays soaked instead
+    pub into: MaterialId,
+}
+
+/// Losing moisture over time and turning back into a dry material
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drying {
+    /// Ticks a soaked cell takes to dry out at ambient temperature, heat
+    /// speeds it up
+    pub time: u16,
+    pub into: MaterialId,
+}
+
+/// Dissolving into a neighbouring liquid, like salt into water
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dissolving {
+    pub solvent: MaterialId,
+    /// What the solvent turns into, which keeps taking in more until it
+    /// reaches its `saturation`
+    pub into: MaterialId,
+}
+
+/// How a material blows up, see `World::explode`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Explosive {
+    /// Reach of the blast, in cells
+    pub radius: f32,
+    /// Force at the centre of the blast, compared against `hardness`
+    pub power: f32,
+    /// Detonates when heated above this point or when touching something
+    /// that ignites
+    pub detonation_point: f32,
+}
+
+/// Properties of a material as described in the definition file.
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub name: String,
+    /// Number key that selects the material
+    pub key: Option<u8>,
+    pub phase: Phase,
+    /// Relative weight, denser materials sink through lighter fluids
+    pub density: f32,
+    pub colors: Vec<Color>,
+    /// Cells a pile of powder drops for every cell it spreads sideways,
+    /// worked out from the angle of repose. Below 1 piles spread further
+    /// than they drop.
+    pub slope: f32,
+    /// Chance each tick that a powder grain at rest holds its place
+    /// instead of sliding down a slope
+    pub friction: f32,
+    /// Cells a liquid may flow sideways in a single tick
+    pub dispersion: i32,
+    /// Chance each tick that a liquid holds still instead of spreading
+    pub viscosity: f32,
+    /// Share of a temperature difference evened out per tick, 0 to 1
+    pub conductivity: f32,
+    /// Temperature new cells start at
+    pub temperature: f32,
+    /// Turns into another material when heated above this point
+    pub melting: Option<Transition>,
+    pub boiling: Option<Transition>,
+    /// Turns into another material when cooled below this point
+    pub freezing: Option<Transition>,
+    /// Sets fire to flammable materials it touches
+    pub ignites: bool,
+    /// Chance each tick of catching fire while touching something that ignites
+    pub flammability: f32,
+    /// Blast force the material withstands without being destroyed
+    pub hardness: f32,
+    pub explosive: Option<Explosive>,
+    /// Health taken from each touching cell it eats into per tick, 0 for
+    /// anything that is not an acid
+    pub corrosion: u8,
+    /// What acid and the cells it dissolves turn into
+    pub corrodes_into: MaterialId,
+    /// Chance of shrugging off acid each tick, 1 is immune
+    pub corrosion_resistance: f32,
+    /// Drawn brighter the hotter it is
+    pub glows: bool,
+    /// Turns into this once it comes to rest, like a seed sprouting
+    pub settles_into: Option<MaterialId>,
+    pub compaction: Option<Compaction>,
+    pub absorbs: Option<Absorption>,
+    pub drying: Option<Drying>,
+    pub dissolving: Option<Dissolving>,
+    /// Most cells that can dissolve into a single cell of this material
+    pub saturation: u8,
+    /// Most ticks a cell lasts before it decays, `None` lasts forever
+    pub lifetime: Option<u16>,
+    /// What a cell can leave behind when its lifetime runs out, one is
+    /// picked at random and an empty list leaves nothing
+    pub decays_into: Vec<MaterialId>,
+}
+
+impl Material {
+    /// How cells of this material move, shared by every material of a phase
+    pub fn behaviour(&self) -> &'static dyn Block {
+        match self.phase {
+            Phase::Solid => &Solid,
+            Phase::Powder => &Powder,
+            Phase::Liquid => &Liquid,
+            Phase::Gas => &Gas,
+            Phase::Fire => &Fire,
+        }
+    }
+
+    pub fn get_color(&self, shade: u8) -> Color {
+        self.colors[shade as usize % self.colors.len()]
+    }
+}
@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+/// State of matter, decides how a material moves and which materials can
+/// flow through each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Solid,
+    Powder,
+    Liquid,
+    Gas,
+    Fire,
+}
+
+impl Phase {
+    /// Fluids can be displaced by anything denser.
+    pub fn is_fluid(&self) -> bool {
+        matches!(self, Phase::Liquid | Phase::Gas)
+    }
+}
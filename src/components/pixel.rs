@@ -1,6 +1,6 @@
 use ggez::graphics::{self, Color, MeshBuilder};
 
-use super::grid_position::GridPosition;
+use super::{cell_size::CellSize, grid_position::GridPosition};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Pixel {
@@ -13,11 +13,11 @@ impl Pixel {
         Self { position, color }
     }
 
-    pub fn append_to_mesh(&self, mesh_builder: &mut MeshBuilder) {
+    pub fn append_to_mesh(&self, mesh_builder: &mut MeshBuilder, cell_size: CellSize) {
         mesh_builder
             .rectangle(
                 graphics::DrawMode::fill(),
-                self.position.as_rect(),
+                self.position.as_rect(cell_size),
                 self.color,
             )
             .unwrap();
@@ -1,9 +1,11 @@
 #![allow(private_interfaces)]
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
 
-use blocks::sand::Sand;
-use blocks::stone::Stone;
 use ggez::conf::WindowMode;
 use ggez::event::{self, EventHandler, MouseButton};
 use ggez::glam::*;
@@ -15,22 +17,46 @@ use ggez::{Context, ContextBuilder, GameResult};
 
 mod components {
     pub mod block_types;
+    pub mod bounds;
+    pub mod cell;
     pub mod cell_size;
     pub mod directions;
     pub mod grid_position;
+    pub mod material;
+    pub mod phase;
     pub mod pixel;
 }
 mod blocks {
     pub mod block;
-    pub mod sand;
-    pub mod stone;
+    pub mod fire;
+    pub mod gas;
+    pub mod liquid;
+    pub mod powder;
+    pub mod solid;
+}
+mod simulation {
+    pub mod chunk;
+    pub mod context;
+    pub mod grid;
+    pub mod heat;
+    pub mod reaction;
+    pub mod registry;
+    pub mod rng;
+    pub mod save;
+    pub mod world;
 }
-
-use blocks::block::Block;
 
 use components::block_types::BlockType;
+use components::bounds::Bounds;
 use components::cell_size::CellSize;
 use components::grid_position::GridPosition;
+use components::material::MaterialId;
+use components::pixel::Pixel;
+
+use simulation::heat::AMBIENT_TEMPERATURE;
+use simulation::registry::{MaterialRegistry, ERASER_KEY};
+use simulation::save;
+use simulation::world::World;
 
 const CELL_SIZE: CellSize = CellSize {
     width: 8.0,
@@ -39,57 +65,159 @@ const CELL_SIZE: CellSize = CellSize {
 
 const FPS: u32 = 60;
 
+/// Force of a blast set off with the X key, enough to break stone
+const EXPLOSION_POWER: f32 = 12.0;
+
+/// Temperature glowing materials start to brighten at
+const GLOW_TEMPERATURE: f32 = 500.0;
+
+const DEFAULT_SCENE_PATH: &str = "scene.txt";
+const DEFAULT_MATERIALS_PATH: &str = "resources/materials.toml";
+
+/// Command line options, `--seed <number>`, `--scene <path>` and
+/// `--materials <path>`
+struct Arguments {
+    seed: Option<u64>,
+    scene_path: Option<PathBuf>,
+    materials_path: PathBuf,
+}
+
+impl Arguments {
+    fn parse() -> Arguments {
+        let mut arguments = Arguments {
+            seed: None,
+            scene_path: None,
+            materials_path: PathBuf::from(DEFAULT_MATERIALS_PATH),
+        };
+
+        let mut args = env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => match args.next().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => arguments.seed = Some(seed),
+                    _ => {
+                        eprintln!("--seed needs a whole number between 0 and {}", u64::MAX);
+                        process::exit(1);
+                    }
+                },
+                "--scene" => {
+                    arguments.scene_path = args.next().map(PathBuf::from);
+                }
+                "--materials" => {
+                    if let Some(path) = args.next() {
+                        arguments.materials_path = PathBuf::from(path);
+                    }
+                }
+                _ => println!("Unknown argument: {}", arg),
+            }
+        }
+
+        arguments
+    }
+}
+
 struct MainState {
-    blocks: HashMap<GridPosition, Box<dyn Block>>,
+    world: World,
     place_range: i32,
     cell_size: CellSize,
     grid_color: Color,
     mouse_down: bool,
     selected_block: BlockType,
+    scene_path: PathBuf,
+    show_chunks: bool,
+    show_heat: bool,
 }
 
 impl MainState {
-    pub fn new(_ctx: &mut Context) -> MainState {
-        let blocks: HashMap<GridPosition, Box<dyn Block>> = HashMap::new();
+    pub fn new(ctx: &mut Context, arguments: Arguments) -> MainState {
         let cell_size = CELL_SIZE;
+        let (width, height) = ctx.gfx.drawable_size();
+        let bounds = Bounds::from_pixels(width, height, cell_size);
+
+        let materials = match MaterialRegistry::load(&arguments.materials_path) {
+            Ok(materials) => Arc::new(materials),
+            Err(error) => {
+                eprintln!(
+                    "Could not load materials from {}: {}",
+                    arguments.materials_path.display(),
+                    error
+                );
+                process::exit(1);
+            }
+        };
+
+        let seed = arguments.seed.unwrap_or_else(rand::random);
+        let world = World::new(bounds, seed, materials);
 
-        MainState {
-            blocks,
+        let mut state = MainState {
+            world,
             cell_size,
             grid_color: Color::BLACK,
             mouse_down: false,
-            selected_block: BlockType::Sand,
+            selected_block: BlockType::Eraser,
             place_range: 5,
-        }
-    }
+            scene_path: PathBuf::from(DEFAULT_SCENE_PATH),
+            show_chunks: false,
+            show_heat: false,
+        };
 
-    fn clone_blocks(&self) -> HashMap<GridPosition, Box<dyn Block>> {
-        let mut cloned_blocks: HashMap<GridPosition, Box<dyn Block>> = HashMap::new();
+        state.selected_block = state.default_block();
 
-        for (_position, block) in self.blocks.iter() {
-            cloned_blocks.insert(block.get_position(), block.box_clone());
+        if let Some(scene_path) = arguments.scene_path {
+            state.scene_path = scene_path;
+            state.load_scene();
         }
 
-        cloned_blocks
+        println!("Seed: {}", state.world.seed());
+
+        state
+    }
+
+    /// The first material in the definition file
+    fn default_block(&self) -> BlockType {
+        match self.world.materials().iter().next() {
+            Some((id, _material)) => BlockType::Material(id),
+            None => BlockType::Eraser,
+        }
     }
 
-    fn update_positions(&mut self) {
-        let mut new_blocks: HashMap<GridPosition, Box<dyn Block>> = HashMap::new();
+    /// Steps through the materials in file order, wrapping around
+    fn cycle_material(&mut self, step: i32) {
+        let ids: Vec<MaterialId> = self.world.materials().iter().map(|(id, _)| id).collect();
 
-        for (_position, block) in self.blocks.iter_mut() {
-            let position = block.get_position();
-            new_blocks.insert(position, block.box_clone());
+        if ids.is_empty() {
+            return;
         }
 
-        self.blocks = new_blocks;
+        let current = self
+            .selected_block
+            .material()
+            .and_then(|material| ids.iter().position(|&id| id == material))
+            .unwrap_or(0) as i32;
+
+        let next = (current + step).rem_euclid(ids.len() as i32) as usize;
+        self.selected_block = BlockType::Material(ids[next]);
     }
 
-    fn position_occupied(&self, position: GridPosition) -> bool {
-        self.blocks.contains_key(&position)
+    fn save_scene(&self) {
+        match save::save(&self.world, &self.scene_path) {
+            Ok(()) => println!("Saved scene to {}", self.scene_path.display()),
+            Err(error) => println!("Could not save scene: {}", error),
+        }
     }
 
-    fn insert_block(&mut self, block: Box<dyn Block>) {
-        self.blocks.insert(block.get_position(), block);
+    /// Replaces the world with the saved one, keeping the size it was saved
+    /// at so it replays the same whatever the window size
+    fn load_scene(&mut self) {
+        match save::load(&self.scene_path, self.world.materials().clone()) {
+            Ok(world) => {
+                self.world = world;
+
+                println!("Loaded scene from {}", self.scene_path.display());
+            }
+            Err(error) => println!("Could not load scene: {}", error),
+        }
     }
 
     fn draw_grid(&mut self, ctx: &mut Context, canvas: &mut Canvas) {
@@ -149,56 +277,104 @@ impl MainState {
         fps_text.draw(canvas, DrawParam::default());
     }
 
-    fn draw_pixels(&mut self, ctx: &mut Context, canvas: &mut Canvas) {
+    /// Name of the selected material in the top right corner
+    fn draw_selected(&mut self, ctx: &mut Context, canvas: &mut Canvas) {
+        let name = match self.selected_block {
+            BlockType::Material(id) => self
+                .world
+                .materials()
+                .get(id)
+                .map_or("Unknown", |material| material.name.as_str()),
+            BlockType::Eraser => "Eraser",
+        };
+
+        let mut selected_text = Text::new(name);
+        selected_text.set_scale(PxScale::from(18.0));
+
+        let (width, _height) = ctx.gfx.drawable_size();
+        let text_width = selected_text.measure(ctx).map_or(0.0, |size| size.x);
+
+        selected_text.draw(
+            canvas,
+            DrawParam::default().dest(Vec2::new(width - text_width - 4.0, 0.0)),
+        );
+    }
+
+    /// Outlines the dirty rectangle of every awake chunk
+    fn draw_chunks(&mut self, ctx: &mut Context, canvas: &mut Canvas) {
+        let chunks = self.world.chunks();
         let mut mesh_builder = graphics::MeshBuilder::new();
 
-        for (_pos, block) in self.blocks.iter_mut() {
-            block.append_to_mesh(&mut mesh_builder);
+        for row in 0..chunks.rows() {
+            for column in 0..chunks.columns() {
+                let rect = match chunks.get(column, row).and_then(|chunk| chunk.dirty_rect()) {
+                    Some(rect) => rect,
+                    None => continue,
+                };
+
+                let min = rect.min.as_vec2(self.cell_size);
+                let max = (rect.max + GridPosition::new(1, 1)).as_vec2(self.cell_size);
+
+                mesh_builder
+                    .rectangle(
+                        DrawMode::stroke(1.0),
+                        Rect::new(min.x, min.y, max.x - min.x, max.y - min.y),
+                        Color::RED,
+                    )
+                    .unwrap();
+            }
         }
 
-        let pixel_mesh_data = mesh_builder.build();
-        let pixel_mesh = Mesh::from_data(ctx, pixel_mesh_data);
+        if chunks.awake_count() > 0 {
+            let mesh = Mesh::from_data(ctx, mesh_builder.build());
+            mesh.draw(canvas, DrawParam::default());
+        }
 
-        pixel_mesh.draw(canvas, DrawParam::default());
+        let mut chunk_text = Text::new(format!("Awake chunks: {}", chunks.awake_count()));
+        chunk_text.set_scale(PxScale::from(18.0));
+        chunk_text.draw(canvas, DrawParam::default().dest(Vec2::new(0.0, 20.0)));
     }
 
-    fn spawn_block(&mut self, block_type: BlockType, grid_position: GridPosition) -> GameResult {
-        if self.position_occupied(grid_position) {
-            return Ok(());
-        }
+    fn draw_pixels(&mut self, ctx: &mut Context, canvas: &mut Canvas) {
+        let mut mesh_builder = graphics::MeshBuilder::new();
 
-        let block: Box<dyn Block>;
+        let materials = self.world.materials();
 
-        match block_type {
-            BlockType::Sand => {
-                block = Box::new(Sand::new(grid_position));
-            }
-            BlockType::Stone => {
-                block = Box::new(Stone::new(grid_position));
-            }
-            BlockType::Eraser => {
-                return Ok(());
-            }
+        for (position, cell) in self.world.grid().iter() {
+            let material = match materials.get(cell.material) {
+                Some(material) => material,
+                None => continue,
+            };
+
+            let color = if self.show_heat {
+                heat_color(cell.temperature)
+            } else if material.glows {
+                glow_color(material.get_color(cell.shade), cell.temperature)
+            } else {
+                material.get_color(cell.shade)
+            };
+
+            let pixel = Pixel::new(position, color);
+
+            pixel.append_to_mesh(&mut mesh_builder, self.cell_size);
         }
 
-        self.insert_block(block);
+        let pixel_mesh_data = mesh_builder.build();
+        let pixel_mesh = Mesh::from_data(ctx, pixel_mesh_data);
 
-        Ok(())
+        pixel_mesh.draw(canvas, DrawParam::default());
     }
 
-    /// Range is in number of cells
-    fn generate_positions(
-        &self,
-        start_position: GridPosition,
-        range: i32,
-    ) -> HashSet<GridPosition> {
-        let mut positions: HashSet<GridPosition> = HashSet::new();
+    /// Range is in number of cells. Listed in a fixed order, blocks draw
+    /// from the world's RNG as they spawn so replays depend on it.
+    fn generate_positions(&self, start_position: GridPosition, range: i32) -> Vec<GridPosition> {
+        let mut positions = Vec::new();
 
         for x in -range..range {
             for y in -range..range {
-                let position = start_position + GridPosition::new(x, y, self.cell_size);
+                let position = start_position + GridPosition::new(x, y);
 
-                positions.insert(position);
+                positions.push(position);
             }
         }
 
@@ -218,7 +394,7 @@ impl MainState {
             let x = rand::random::<i32>() % range;
             let y = rand::random::<i32>() % range;
 
-            let position = start_position + GridPosition::new(x, y, self.cell_size);
+            let position = start_position + GridPosition::new(x, y);
 
             positions.insert(position);
         }
@@ -233,7 +409,7 @@ impl MainState {
         position: GridPosition,
         radius: f32,
     ) {
-        let vec2 = position.as_vec2();
+        let vec2 = position.as_vec2(self.cell_size);
 
         let width = self.cell_size.width * (radius * 2.0);
         let height = self.cell_size.height * (radius * 2.0);
@@ -264,16 +440,7 @@ impl MainState {
 impl EventHandler for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
         while ctx.time.check_update_time(FPS) {
-            let cloned_blocks = self.clone_blocks();
-
-            // Unmutable updates
-            for (_position, block) in self.blocks.iter_mut() {
-                block.apply_gravity(ctx, &cloned_blocks, self.cell_size);
-                block.apply_motion(ctx, &cloned_blocks, self.cell_size);
-            }
-
-            // Mutable updates
-            for (_position, _block) in self.blocks.iter() {}
+            self.world.tick();
 
             let mouse_position = ctx.mouse.position();
 
@@ -282,7 +449,7 @@ impl EventHandler for MainState {
                 let positions = self.generate_positions(grid_position, self.place_range);
 
                 for position in positions {
-                    self.spawn_block(self.selected_block.clone(), position)?;
+                    self.world.spawn_block(self.selected_block, position);
                 }
             }
 
@@ -291,11 +458,9 @@ impl EventHandler for MainState {
                 let positions = self.generate_positions(grid_position, self.place_range);
 
                 for position in positions {
-                    self.blocks.remove(&position);
+                    self.world.remove_block(position);
                 }
             }
-
-            self.update_positions();
         }
 
         Ok(())
@@ -304,28 +469,14 @@ impl EventHandler for MainState {
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         let mut canvas = Canvas::from_frame(ctx, Color::BLACK);
 
-        // Overlap checking ---------------------------------
-
-        let mut position_count: HashMap<GridPosition, u64> = HashMap::new();
-
-        for (_pos, block) in self.blocks.iter_mut() {
-            let position = block.get_position();
-            let count = position_count.get(&position).unwrap_or(&0) + 1;
-
-            position_count.insert(position, count);
-        }
-
-        for (position, count) in position_count {
-            if count > 1 {
-                println!("Position: {:?} Count: {}", position, count);
-            }
-        }
-
-        // End overlap checking -----------------------------
-
         self.draw_grid(ctx, &mut canvas);
         self.draw_fps(ctx, &mut canvas);
         self.draw_pixels(ctx, &mut canvas);
+        self.draw_selected(ctx, &mut canvas);
+
+        if self.show_chunks {
+            self.draw_chunks(ctx, &mut canvas);
+        }
 
         self.draw_spawnbox(
             ctx,
@@ -339,16 +490,31 @@ impl EventHandler for MainState {
 
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         input: ggez::input::keyboard::KeyInput,
         _repeated: bool,
     ) -> GameResult {
         if let Some(key_code) = input.keycode {
             match key_code {
-                KeyCode::Key1 => self.selected_block = BlockType::Sand,
-                KeyCode::Key2 => self.selected_block = BlockType::Stone,
-                KeyCode::Key3 => self.selected_block = BlockType::Eraser,
-                _ => self.selected_block = BlockType::Sand,
+                KeyCode::C => self.show_chunks = !self.show_chunks,
+                KeyCode::H => self.show_heat = !self.show_heat,
+                KeyCode::Q => self.cycle_material(-1),
+                KeyCode::E => self.cycle_material(1),
+                KeyCode::X => {
+                    let position = GridPosition::from_vec2(ctx.mouse.position(), self.cell_size);
+                    self.world
+                        .explode(position, self.place_range as f32, EXPLOSION_POWER);
+                }
+                KeyCode::F5 => self.save_scene(),
+                KeyCode::F9 => self.load_scene(),
+                _ => {
+                    self.selected_block = match digit(key_code) {
+                        Some(ERASER_KEY) => BlockType::Eraser,
+                        key => key
+                            .and_then(|key| self.world.materials().by_key(key))
+                            .map_or_else(|| self.default_block(), BlockType::Material),
+                    };
+                }
             }
         }
 
@@ -379,6 +545,23 @@ impl EventHandler for MainState {
         Ok(())
     }
 
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        let bounds = Bounds::from_pixels(width, height, self.cell_size);
+
+        // Minimising reports a zero size, which would wipe the world. Any
+        // other size is followed so the window edge stays the floor, cells
+        // outside a shrunk window are lost.
+        if bounds.width == 0 || bounds.height == 0 {
+            return Ok(());
+        }
+
+        if bounds != self.world.grid().bounds() {
+            self.world.resize(bounds);
+        }
+
+        Ok(())
+    }
+
     fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) -> GameResult {
         // println!("Mousewheel event, x: {x}, y: {y}");
         if y > 0.0 {
@@ -395,13 +578,59 @@ impl EventHandler for MainState {
     }
 }
 
+/// Lightens a colour towards a hot yellow-white, starting at
+/// `GLOW_TEMPERATURE` and reaching full brightness 1000 degrees above it.
+fn glow_color(color: Color, temperature: f32) -> Color {
+    let glow = ((temperature - GLOW_TEMPERATURE) / 1000.0).clamp(0.0, 1.0) * 0.6;
+
+    Color::new(
+        color.r + (1.0 - color.r) * glow,
+        color.g + (0.9 - color.g).max(0.0) * glow,
+        color.b + (0.5 - color.b).max(0.0) * glow,
+        color.a,
+    )
+}
+
+/// Grey at room temperature, shading to blue when colder and through red
+/// to yellow when hotter.
+fn heat_color(temperature: f32) -> Color {
+    if temperature < AMBIENT_TEMPERATURE {
+        let cold = ((AMBIENT_TEMPERATURE - temperature) / 50.0).clamp(0.0, 1.0);
+
+        Color::new(0.2 - 0.2 * cold, 0.2 - 0.2 * cold, 0.2 + 0.8 * cold, 1.0)
+    } else {
+        let hot = ((temperature - AMBIENT_TEMPERATURE) / 1000.0).clamp(0.0, 1.0);
+
+        Color::new(0.2 + 0.8 * hot, 0.2 + 0.8 * hot * hot, 0.2 - 0.2 * hot, 1.0)
+    }
+}
+
+/// Number on a digit key
+fn digit(key_code: KeyCode) -> Option<u8> {
+    let digit = match key_code {
+        KeyCode::Key0 => 0,
+        KeyCode::Key1 => 1,
+        KeyCode::Key2 => 2,
+        KeyCode::Key3 => 3,
+        KeyCode::Key4 => 4,
+        KeyCode::Key5 => 5,
+        KeyCode::Key6 => 6,
+        KeyCode::Key7 => 7,
+        KeyCode::Key8 => 8,
+        KeyCode::Key9 => 9,
+        _ => return None,
+    };
+
+    Some(digit)
+}
+
 fn main() {
     let (mut ctx, event_loop) = ContextBuilder::new("Sand Simulation v3", "")
         .window_setup(ggez::conf::WindowSetup::default().title("Sand Simulation v3"))
         .build()
         .expect("Could not create ggez context");
 
-    let state = MainState::new(&mut ctx);
+    let state = MainState::new(&mut ctx, Arguments::parse());
 
     ctx.gfx
         .set_mode(WindowMode {
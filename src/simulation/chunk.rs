@@ -0,0 +1,202 @@
+use crate::components::{bounds::Bounds, grid_position::GridPosition};
+
+/// Width and height of a chunk, in cells
+pub const CHUNK_SIZE: i32 = 32;
+
+/// Furthest a cell update may read or write outside its own chunk, half a
+/// chunk keeps chunks updated in the same phase from overlapping.
+pub const MAX_REACH: i32 = CHUNK_SIZE / 2;
+
+/// Chunks are updated in four passes of a 2x2 checkerboard so that no two
+/// chunks updated at the same time are neighbours.
+pub const PHASES: [(i32, i32); 4] = [(0, 0), (1, 0), (0, 1), (1, 1)];
+
+/// Inclusive area of cells that need updating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub min: GridPosition,
+    pub max: GridPosition,
+}
+
+impl DirtyRect {
+    pub fn new(position: GridPosition) -> Self {
+        Self {
+            min: position,
+            max: position,
+        }
+    }
+
+    pub fn include(&mut self, position: GridPosition) {
+        self.min.x = self.min.x.min(position.x);
+        self.min.y = self.min.y.min(position.y);
+        self.max.x = self.max.x.max(position.x);
+        self.max.y = self.max.y.max(position.y);
+    }
+}
+
+/// A fixed-size block of cells. A chunk sleeps while nothing inside it
+/// changed last tick and is skipped entirely by the update loop.
+#[derive(Debug, Clone, Default)]
+pub struct Chunk {
+    /// Cells to update this tick
+    dirty: Option<DirtyRect>,
+    /// Cells that changed this tick, updated on the next one
+    next_dirty: Option<DirtyRect>,
+}
+
+impl Chunk {
+    pub fn dirty_rect(&self) -> Option<DirtyRect> {
+        self.dirty
+    }
+
+    fn mark(&mut self, position: GridPosition) {
+        match &mut self.next_dirty {
+            Some(rect) => rect.include(position),
+            None => self.next_dirty = Some(DirtyRect::new(position)),
+        }
+    }
+}
+
+pub struct ChunkMap {
+    bounds: Bounds,
+    columns: i32,
+    rows: i32,
+    chunks: Vec<Chunk>,
+}
+
+impl ChunkMap {
+    pub fn new(bounds: Bounds) -> Self {
+        let columns = (bounds.width.max(0) + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let rows = (bounds.height.max(0) + CHUNK_SIZE - 1) / CHUNK_SIZE;
+
+        Self {
+            bounds,
+            columns,
+            rows,
+            chunks: vec![Chunk::default(); (columns * rows) as usize],
+        }
+    }
+
+    pub fn columns(&self) -> i32 {
+        self.columns
+    }
+
+    pub fn rows(&self) -> i32 {
+        self.rows
+    }
+
+    /// Chunk at the given chunk column and row
+    pub fn get(&self, column: i32, row: i32) -> Option<&Chunk> {
+        if column < 0 || row < 0 || column >= self.columns || row >= self.rows {
+            return None;
+        }
+
+        self.chunks.get((row * self.columns + column) as usize)
+    }
+
+    fn chunk_mut(&mut self, position: GridPosition) -> Option<&mut Chunk> {
+        if !self.bounds.contains(position) {
+            return None;
+        }
+
+        let column = position.x / CHUNK_SIZE;
+        let row = position.y / CHUNK_SIZE;
+
+        self.chunks.get_mut((row * self.columns + column) as usize)
+    }
+
+    /// Schedules the cell and its neighbours for the next tick, waking
+    /// any chunk they fall in.
+    pub fn wake(&mut self, position: GridPosition) {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let position = position + GridPosition::new(x, y);
+
+                if let Some(chunk) = self.chunk_mut(position) {
+                    chunk.mark(position);
+                }
+            }
+        }
+    }
+
+    /// Schedules every cell up to `radius` columns either side of the
+    /// position, on its row and the rows above and below. Liquids look
+    /// that far along for somewhere to flow, so one leaving a cell can
+    /// open the way for others further off.
+    pub fn wake_row(&mut self, position: GridPosition, radius: i32) {
+        let left = (position.x - radius).max(0);
+        let right = (position.x + radius).min(self.bounds.width - 1);
+
+        for y in position.y - 1..=position.y + 1 {
+            let mut start = left;
+
+            // Marking both ends of the span within each chunk covers it
+            while start <= right {
+                let end = ((start / CHUNK_SIZE + 1) * CHUNK_SIZE - 1).min(right);
+
+                for x in [start, end] {
+                    let position = GridPosition::new(x, y);
+
+                    if let Some(chunk) = self.chunk_mut(position) {
+                        chunk.mark(position);
+                    }
+                }
+
+                start = end + 1;
+            }
+        }
+    }
+
+    /// Every cell covered by the chunk at the given index
+    pub fn area(&self, index: usize) -> DirtyRect {
+        let column = index as i32 % self.columns;
+        let row = index as i32 / self.columns;
+
+        let min = GridPosition::new(column * CHUNK_SIZE, row * CHUNK_SIZE);
+        let max = GridPosition::new(
+            (min.x + CHUNK_SIZE).min(self.bounds.width) - 1,
+            (min.y + CHUNK_SIZE).min(self.bounds.height) - 1,
+        );
+
+        DirtyRect { min, max }
+    }
+
+    pub fn wake_all(&mut self) {
+        for index in 0..self.chunks.len() {
+            self.chunks[index].next_dirty = Some(self.area(index));
+        }
+    }
+
+    /// Makes last tick's changes the work for this tick.
+    pub fn begin_tick(&mut self) {
+        for chunk in self.chunks.iter_mut() {
+            chunk.dirty = chunk.next_dirty.take();
+        }
+    }
+
+    /// Awake chunks in the given checkerboard phase with their index and
+    /// dirty rectangle.
+    pub fn awake_in_phase(&self, phase: (i32, i32)) -> Vec<(usize, DirtyRect)> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, chunk)| {
+                let column = index as i32 % self.columns;
+                let row = index as i32 / self.columns;
+
+                if (column % 2, row % 2) != phase {
+                    return None;
+                }
+
+                chunk.dirty.map(|rect| (index, rect))
+            })
+            .collect()
+    }
+
+    pub fn awake_count(&self) -> usize {
+        self.chunks
+            .iter()
+            .filter(|chunk| chunk.dirty.is_some())
+            .count()
+    }
+}
@@ -0,0 +1,62 @@
+use strum::IntoEnumIterator;
+
+use crate::components::{
+    cell::Cell, directions::Direction, grid_position::GridPosition, material::Material,
+};
+
+use super::{chunk::MAX_REACH, grid::SharedGrid, registry::MaterialRegistry, rng::SimulationRng};
+
+/// Everything a block can see and change while it updates.
+pub struct UpdateContext<'a> {
+    pub grid: SharedGrid<'a>,
+    pub materials: &'a MaterialRegistry,
+    pub rng: SimulationRng,
+    /// Where the cell being updated started the tick
+    pub origin: GridPosition,
+}
+
+impl<'a> UpdateContext<'a> {
+    pub fn material(&self, cell: &Cell) -> Option<&Material> {
+        self.materials.get(cell.material)
+    }
+
+    /// Cells that can still be looked at past the position without going
+    /// further than `MAX_REACH` from the cell being updated
+    pub fn reach_left(&self, position: GridPosition) -> i32 {
+        MAX_REACH - self.origin.distance(position)
+    }
+
+    /// Material of the cell at the position, `None` if empty or outside the grid
+    pub fn material_at(&self, position: GridPosition) -> Option<&Material> {
+        self.grid
+            .get(position)
+            .and_then(|cell| self.materials.get(cell.material))
+    }
+
+    /// The eight cells around the position that lie inside the grid, along
+    /// with where they are. Holds no borrow of the context so cells can be
+    /// changed while walking it.
+    pub fn neighbours(
+        &self,
+        position: GridPosition,
+    ) -> impl Iterator<Item = (GridPosition, Cell)> + 'a {
+        let grid = self.grid;
+
+        Direction::iter()
+            .filter(|&direction| direction != Direction::Middle)
+            .filter_map(move |direction| {
+                let neighbour_position = position + direction.get_offset();
+
+                grid.get(neighbour_position)
+                    .map(|neighbour| (neighbour_position, neighbour))
+            })
+    }
+
+    /// Whether any of the eight surrounding cells is made of a material
+    /// matching the predicate
+    pub fn touching(&self, position: GridPosition, predicate: impl Fn(&Material) -> bool) -> bool {
+        self.neighbours(position)
+            .filter_map(|(_, neighbour)| self.materials.get(neighbour.material))
+            .any(predicate)
+    }
+}
@@ -0,0 +1,162 @@
+use std::marker::PhantomData;
+
+use crate::components::{bounds::Bounds, cell::Cell, grid_position::GridPosition};
+
+use super::chunk::{DirtyRect, MAX_REACH};
+
+/// Dense row-major storage for every cell in the world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    bounds: Bounds,
+    cells: Vec<Cell>,
+}
+
+impl Grid {
+    pub fn new(bounds: Bounds) -> Self {
+        let len = (bounds.width.max(0) * bounds.height.max(0)) as usize;
+
+        Self {
+            bounds,
+            cells: vec![Cell::EMPTY; len],
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn index(&self, position: GridPosition) -> Option<usize> {
+        if !self.bounds.contains(position) {
+            return None;
+        }
+
+        Some((position.y * self.bounds.width + position.x) as usize)
+    }
+
+    pub fn get(&self, position: GridPosition) -> Option<Cell> {
+        self.index(position).map(|index| self.cells[index])
+    }
+
+    /// Writes a cell, ignoring positions outside the grid.
+    pub fn set(&mut self, position: GridPosition, cell: Cell) {
+        if let Some(index) = self.index(position) {
+            self.cells[index] = cell;
+        }
+    }
+
+    /// Out of bounds positions are never considered empty.
+    pub fn is_empty(&self, position: GridPosition) -> bool {
+        self.get(position).is_some_and(|cell| cell.is_empty())
+    }
+
+    /// Iterates every non-empty cell along with its position.
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition, &Cell)> {
+        let width = self.bounds.width;
+
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_index, cell)| !cell.is_empty())
+            .map(move |(index, cell)| {
+                let index = index as i32;
+
+                (GridPosition::new(index % width, index / width), cell)
+            })
+    }
+
+    /// Lets several threads update the grid at once, see `SharedGrid`.
+    pub fn shared(&mut self) -> SharedGrid<'_> {
+        SharedGrid {
+            cells: self.cells.as_mut_ptr(),
+            bounds: self.bounds,
+            reach: DirtyRect {
+                min: GridPosition::new(i32::MIN, i32::MIN),
+                max: GridPosition::new(i32::MAX, i32::MAX),
+            },
+            _grid: PhantomData,
+        }
+    }
+
+    /// Creates a grid with new bounds, keeping every cell that still fits.
+    pub fn resized(&self, bounds: Bounds) -> Self {
+        let mut grid = Grid::new(bounds);
+
+        for (position, cell) in self.iter() {
+            grid.set(position, *cell);
+        }
+
+        grid
+    }
+}
+
+/// Mutable access to a grid from every chunk of a checkerboard phase at
+/// once.
+///
+/// Chunks updated together are two chunks apart and a cell never reads or
+/// writes further than `MAX_REACH` outside its own, so no cell is ever
+/// touched by two threads at the same time. Debug builds check this.
+#[derive(Clone, Copy)]
+pub struct SharedGrid<'a> {
+    cells: *mut Cell,
+    bounds: Bounds,
+    /// Cells this copy may touch, see `SharedGrid::limited_to`
+    reach: DirtyRect,
+    _grid: PhantomData<&'a mut Grid>,
+}
+
+// SAFETY: threads only ever access disjoint cells, see the type docs.
+unsafe impl Send for SharedGrid<'_> {}
+unsafe impl Sync for SharedGrid<'_> {}
+
+impl SharedGrid<'_> {
+    /// The same grid for updating the cells of one chunk, which may only
+    /// touch cells up to `MAX_REACH` outside of it
+    pub fn limited_to(self, chunk: DirtyRect) -> Self {
+        let reach = GridPosition::new(MAX_REACH, MAX_REACH);
+
+        Self {
+            reach: DirtyRect {
+                min: GridPosition::new(chunk.min.x - reach.x, chunk.min.y - reach.y),
+                max: chunk.max + reach,
+            },
+            ..self
+        }
+    }
+
+    fn index(&self, position: GridPosition) -> Option<usize> {
+        debug_assert!(
+            (self.reach.min.x..=self.reach.max.x).contains(&position.x)
+                && (self.reach.min.y..=self.reach.max.y).contains(&position.y),
+            "{:?} is out of reach of the chunk being updated",
+            position
+        );
+
+        if !self.bounds.contains(position) {
+            return None;
+        }
+
+        Some((position.y * self.bounds.width + position.x) as usize)
+    }
+
+    pub fn get(&self, position: GridPosition) -> Option<Cell> {
+        // SAFETY: the index is in bounds and no other thread touches this cell.
+        self.index(position)
+            .map(|index| unsafe { *self.cells.add(index) })
+    }
+
+    /// Writes a cell, ignoring positions outside the grid.
+    pub fn set(&self, position: GridPosition, cell: Cell) {
+        if let Some(index) = self.index(position) {
+            // SAFETY: the index is in bounds and no other thread touches this cell.
+            unsafe { *self.cells.add(index) = cell }
+        }
+    }
+
+    /// Exchanges two cells, doing nothing if either is outside the grid.
+    pub fn swap(&self, a: GridPosition, b: GridPosition) {
+        if let (Some(a), Some(b)) = (self.index(a), self.index(b)) {
+            // SAFETY: both indices are in bounds and owned by this thread.
+            unsafe { std::ptr::swap(self.cells.add(a), self.cells.add(b)) }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::components::{
+    cell::Cell, directions::Direction, grid_position::GridPosition, material::MaterialId,
+};
+
+use super::context::UpdateContext;
+
+/// Temperature of empty space and of materials that do not set their own
+pub const AMBIENT_TEMPERATURE: f32 = 20.0;
+
+/// How readily heat passes between a cell and empty space
+const AIR_CONDUCTIVITY: f32 = 0.002;
+
+/// Smallest temperature change worth applying, anything less is dropped so
+/// chunks close to equilibrium can fall asleep.
+const MIN_HEAT_CHANGE: f32 = 0.01;
+
+/// Moves the cell's temperature towards its four direct neighbours, empty
+/// space counts as air at `AMBIENT_TEMPERATURE`. Only the cell itself is
+/// changed, each neighbour evens out from its side when it updates.
+pub fn conduct(cell: &mut Cell, position: GridPosition, ctx: &UpdateContext) {
+    let conductivity = match ctx.material(cell) {
+        Some(material) => material.conductivity,
+        None => return,
+    };
+
+    let mut change = 0.0;
+
+    for direction in [
+        Direction::Top,
+        Direction::Left,
+        Direction::Right,
+        Direction::Bottom,
+    ] {
+        let neighbour_position = position + direction.get_offset();
+
+        let (temperature, rate) = match ctx.grid.get(neighbour_position) {
+            Some(neighbour) => match ctx.material(&neighbour) {
+                Some(material) => (
+                    neighbour.temperature,
+                    (conductivity + material.conductivity) / 2.0,
+                ),
+                None => (AMBIENT_TEMPERATURE, AIR_CONDUCTIVITY),
+            },
+            None => continue,
+        };
+
+        change += (temperature - cell.temperature) * rate / 4.0;
+    }
+
+    if change.abs() >= MIN_HEAT_CHANGE {
+        cell.temperature += change;
+    }
+}
+
+/// The material this cell turns into once it passes a melting, boiling or
+/// freezing point.
+pub fn transition(cell: &Cell, ctx: &UpdateContext) -> Option<MaterialId> {
+    let material = ctx.material(cell)?;
+
+    let transition = [material.melting, material.boiling]
+        .into_iter()
+        .flatten()
+        .find(|transition| cell.temperature > transition.temperature)
+        .or_else(|| {
+            material
+                .freezing
+                .filter(|transition| cell.temperature < transition.temperature)
+        })?;
+
+    Some(transition.into)
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use crate::components::material::MaterialId;
+
+/// What two touching materials turn into, `products.0` replaces the first
+/// reactant and `products.1` the second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reaction {
+    pub products: (MaterialId, MaterialId),
+    /// Chance of reacting each tick the pair touches
+    pub chance: f32,
+}
+
+/// Reactions looked up by the pair of materials touching.
+#[derive(Debug, Default)]
+pub struct ReactionTable {
+    reactions: HashMap<(MaterialId, MaterialId), Vec<Reaction>>,
+    /// Indexed by material id, lets most cells skip the neighbour checks
+    reactive: Vec<bool>,
+}
+
+impl ReactionTable {
+    /// Adds a rule for `first + second`, along with its mirror image so the
+    /// order cells are found in does not matter.
+    pub fn insert(&mut self, reactants: (MaterialId, MaterialId), reaction: Reaction) {
+        let (first, second) = reactants;
+        let mirrored = Reaction {
+            products: (reaction.products.1, reaction.products.0),
+            chance: reaction.chance,
+        };
+
+        self.reactions
+            .entry((first, second))
+            .or_default()
+            .push(reaction);
+
+        if first != second {
+            self.reactions
+                .entry((second, first))
+                .or_default()
+                .push(mirrored);
+        }
+
+        for id in [first, second] {
+            let index = id.0 as usize;
+
+            if self.reactive.len() <= index {
+                self.reactive.resize(index + 1, false);
+            }
+
+            self.reactive[index] = true;
+        }
+    }
+
+    /// Whether the material takes part in any reaction
+    pub fn is_reactive(&self, id: MaterialId) -> bool {
+        self.reactive.get(id.0 as usize).copied().unwrap_or(false)
+    }
+
+    /// Rules for a cell of `first` touching a cell of `second`
+    pub fn get(&self, first: MaterialId, second: MaterialId) -> &[Reaction] {
+        self.reactions
+            .get(&(first, second))
+            .map_or(&[], |reactions| reactions.as_slice())
+    }
+}
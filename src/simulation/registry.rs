@@ -0,0 +1,772 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+
+use ggez::graphics::Color;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::components::{
+    cell::Cell,
+    material::{
+        Absorption, Compaction, Dissolving, Drying, Explosive, Material, MaterialId, Transition,
+    },
+    phase::Phase,
+};
+
+use super::chunk::MAX_REACH;
+use super::heat::AMBIENT_TEMPERATURE;
+use super::reaction::{Reaction, ReactionTable};
+use super::rng::SimulationRng;
+
+/// Name that stands for empty space in reactions
+const EMPTY_NAME: &str = "Empty";
+
+/// Number key that selects the eraser, so no material can use it
+pub const ERASER_KEY: u8 = 3;
+
+/// Layout of the material definition file
+#[derive(Deserialize)]
+struct MaterialFile {
+    /// What cells destroyed by a blast turn into, picked at random
+    #[serde(default)]
+    explosion_debris: Vec<String>,
+    material: Vec<MaterialEntry>,
+    #[serde(default)]
+    reaction: Vec<ReactionEntry>,
+}
+
+#[derive(Deserialize)]
+struct MaterialEntry {
+    name: String,
+    key: Option<u8>,
+    phase: Phase,
+    density: f32,
+    colors: Vec<String>,
+    #[serde(default = "default_angle_of_repose")]
+    angle_of_repose: f32,
+    #[serde(default)]
+    friction: f32,
+    #[serde(default)]
+    dispersion: i32,
+    #[serde(default)]
+    viscosity: f32,
+    #[serde(default = "default_conductivity")]
+    conductivity: f32,
+    #[serde(default = "default_temperature")]
+    temperature: f32,
+    melting_point: Option<f32>,
+    melts_into: Option<String>,
+    boiling_point: Option<f32>,
+    boils_into: Option<String>,
+    freezing_point: Option<f32>,
+    freezes_into: Option<String>,
+    #[serde(default)]
+    ignites: bool,
+    #[serde(default)]
+    flammability: f32,
+    burns_into: Option<String>,
+    #[serde(default)]
+    corrosion: u8,
+    corrodes_into: Option<String>,
+    #[serde(default)]
+    corrosion_resistance: f32,
+    #[serde(default)]
+    glows: bool,
+    #[serde(default)]
+    hardness: f32,
+    blast_radius: Option<f32>,
+    blast_power: Option<f32>,
+    #[serde(default = "default_detonation_point")]
+    detonation_point: f32,
+    settles_into: Option<String>,
+    compacts_into: Option<String>,
+    compaction_depth: Option<i32>,
+    absorbs: Option<String>,
+    absorbs_into: Option<String>,
+    dries_into: Option<String>,
+    drying_time: Option<u16>,
+    dissolves_in: Option<String>,
+    dissolves_into: Option<String>,
+    #[serde(default)]
+    saturation: u8,
+    lifetime: Option<u16>,
+    #[serde(default)]
+    decays_into: Vec<String>,
+}
+
+/// Parts of an entry that name other materials, resolved once every
+/// material has an id
+struct Links {
+    transitions: [(Option<f32>, Option<String>); 3],
+    decays_into: Vec<String>,
+    burns_into: Option<String>,
+    corrodes_into: Option<String>,
+    settles_into: Option<String>,
+    compaction: Option<(i32, String)>,
+    absorption: Option<(String, String)>,
+    drying: Option<(u16, String)>,
+    dissolving: Option<(String, String)>,
+}
+
+fn default_angle_of_repose() -> f32 {
+    45.0
+}
+
+fn default_conductivity() -> f32 {
+    0.1
+}
+
+fn default_temperature() -> f32 {
+    AMBIENT_TEMPERATURE
+}
+
+fn default_detonation_point() -> f32 {
+    150.0
+}
+
+#[derive(Deserialize)]
+struct ReactionEntry {
+    reactants: [String; 2],
+    products: [String; 2],
+    chance: f32,
+}
+
+/// Every material the simulation knows about. Ids are handed out in file
+/// order starting at 1, 0 is always empty space.
+#[derive(Debug)]
+pub struct MaterialRegistry {
+    materials: Vec<Material>,
+    reactions: ReactionTable,
+    explosion_debris: Vec<MaterialId>,
+}
+
+impl MaterialRegistry {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(source: &str) -> io::Result<Self> {
+        let file: MaterialFile =
+            toml::from_str(source).map_err(|error| invalid_data(&error.to_string()))?;
+
+        let mut names = HashSet::new();
+        let mut keys = HashSet::new();
+        let mut materials = Vec::new();
+        let mut links = Vec::new();
+
+        for entry in file.material {
+            if entry.name.is_empty()
+                || entry.name.contains(char::is_whitespace)
+                || entry.name == EMPTY_NAME
+            {
+                return Err(invalid_data(&format!(
+                    "invalid material name {:?}",
+                    entry.name
+                )));
+            }
+
+            if !names.insert(entry.name.clone()) {
+                return Err(invalid_data(&format!("duplicate material {}", entry.name)));
+            }
+
+            if let Some(key) = entry.key {
+                if key > 9 || key == ERASER_KEY {
+                    return Err(invalid_data(&format!(
+                        "{} key {} is not a free number key",
+                        entry.name, key
+                    )));
+                }
+
+                if !keys.insert(key) {
+                    return Err(invalid_data(&format!(
+                        "{} key {} is already taken",
+                        entry.name, key
+                    )));
+                }
+            }
+
+            let colors = entry
+                .colors
+                .iter()
+                .map(|color| parse_color(color))
+                .collect::<io::Result<Vec<Color>>>()?;
+
+            if colors.is_empty() || colors.len() > u8::MAX as usize {
+                return Err(invalid_data(&format!(
+                    "{} needs between 1 and 255 colors",
+                    entry.name
+                )));
+            }
+
+            if !(0.0..=1.0).contains(&entry.conductivity) {
+                return Err(invalid_data(&format!(
+                    "{} conductivity is not between 0 and 1",
+                    entry.name
+                )));
+            }
+
+            if !(entry.angle_of_repose > 0.0 && entry.angle_of_repose < 90.0) {
+                return Err(invalid_data(&format!(
+                    "{} angle_of_repose is not between 0 and 90 degrees",
+                    entry.name
+                )));
+            }
+
+            let compaction = match (entry.compaction_depth, entry.compacts_into) {
+                (Some(depth), Some(into)) if depth > 0 => Some((depth, into)),
+                (None, None) => None,
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "{} needs both a positive compaction_depth and a material to compact into",
+                        entry.name
+                    )))
+                }
+            };
+
+            let absorption = match (entry.absorbs, entry.absorbs_into) {
+                (Some(liquid), Some(into)) => Some((liquid, into)),
+                (None, None) => None,
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "{} needs both a liquid to absorb and a material to turn into",
+                        entry.name
+                    )))
+                }
+            };
+
+            let drying = match (entry.drying_time, entry.dries_into) {
+                (Some(time), Some(into)) if time > 0 => Some((time, into)),
+                (None, None) => None,
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "{} needs both a positive drying_time and a material to dry into",
+                        entry.name
+                    )))
+                }
+            };
+
+            let dissolving = match (entry.dissolves_in, entry.dissolves_into) {
+                (Some(solvent), Some(into)) => Some((solvent, into)),
+                (None, None) => None,
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "{} needs both a liquid to dissolve in and a material to turn it into",
+                        entry.name
+                    )))
+                }
+            };
+
+            if !(0.0..=1.0).contains(&entry.friction) {
+                return Err(invalid_data(&format!(
+                    "{} friction is not between 0 and 1",
+                    entry.name
+                )));
+            }
+
+            if !(0..=MAX_REACH).contains(&entry.dispersion) {
+                return Err(invalid_data(&format!(
+                    "{} dispersion is not between 0 and {}",
+                    entry.name, MAX_REACH
+                )));
+            }
+
+            if !(0.0..=1.0).contains(&entry.viscosity) {
+                return Err(invalid_data(&format!(
+                    "{} viscosity is not between 0 and 1",
+                    entry.name
+                )));
+            }
+
+            if !(0.0..=1.0).contains(&entry.corrosion_resistance) {
+                return Err(invalid_data(&format!(
+                    "{} corrosion_resistance is not between 0 and 1",
+                    entry.name
+                )));
+            }
+
+            if !(0.0..=1.0).contains(&entry.flammability) {
+                return Err(invalid_data(&format!(
+                    "{} flammability is not between 0 and 1",
+                    entry.name
+                )));
+            }
+
+            let explosive = match (entry.blast_radius, entry.blast_power) {
+                (Some(radius), Some(power)) if radius > 0.0 => Some(Explosive {
+                    radius,
+                    power,
+                    detonation_point: entry.detonation_point,
+                }),
+                (None, None) => None,
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "{} needs both a positive blast_radius and a blast_power",
+                        entry.name
+                    )))
+                }
+            };
+
+            if entry.lifetime == Some(0) {
+                return Err(invalid_data(&format!(
+                    "{} lifetime must be above 0",
+                    entry.name
+                )));
+            }
+
+            links.push(Links {
+                transitions: [
+                    (entry.melting_point, entry.melts_into),
+                    (entry.boiling_point, entry.boils_into),
+                    (entry.freezing_point, entry.freezes_into),
+                ],
+                decays_into: entry.decays_into,
+                burns_into: entry.burns_into,
+                corrodes_into: entry.corrodes_into,
+                settles_into: entry.settles_into,
+                compaction,
+                absorption,
+                drying,
+                dissolving,
+            });
+
+            materials.push(Material {
+                name: entry.name,
+                key: entry.key,
+                phase: entry.phase,
+                density: entry.density,
+                colors,
+                // In hundredths so 45 degrees comes out as exactly 1
+                slope: (entry.angle_of_repose.to_radians().tan() * 100.0).round() / 100.0,
+                friction: entry.friction,
+                dispersion: entry.dispersion,
+                viscosity: entry.viscosity,
+                conductivity: entry.conductivity,
+                temperature: entry.temperature,
+                melting: None,
+                boiling: None,
+                freezing: None,
+                ignites: entry.ignites,
+                flammability: entry.flammability,
+                hardness: entry.hardness,
+                explosive,
+                corrosion: entry.corrosion,
+                corrodes_into: MaterialId::EMPTY,
+                corrosion_resistance: entry.corrosion_resistance,
+                glows: entry.glows,
+                settles_into: None,
+                compaction: None,
+                absorbs: None,
+                drying: None,
+                dissolving: None,
+                saturation: entry.saturation,
+                lifetime: entry.lifetime,
+                decays_into: Vec::new(),
+            });
+        }
+
+        if materials.len() >= u16::MAX as usize {
+            return Err(invalid_data("too many materials"));
+        }
+
+        let mut registry = Self {
+            materials,
+            reactions: ReactionTable::default(),
+            explosion_debris: Vec::new(),
+        };
+
+        registry.explosion_debris = file
+            .explosion_debris
+            .iter()
+            .map(|name| registry.resolve(name))
+            .collect::<io::Result<_>>()?;
+
+        for (index, link) in links.iter().enumerate() {
+            let [melting, boiling, freezing] =
+                link.transitions
+                    .clone()
+                    .map(|(temperature, into)| match (temperature, into) {
+                        (Some(temperature), Some(into)) => Ok(Some(Transition {
+                            temperature,
+                            into: registry.resolve(&into)?,
+                        })),
+                        (None, None) => Ok(None),
+                        _ => Err(invalid_data(&format!(
+                            "{} needs both a temperature and a material to turn into",
+                            registry.materials[index].name
+                        ))),
+                    });
+
+            let decays_into = link
+                .decays_into
+                .iter()
+                .map(|name| registry.resolve(name))
+                .collect::<io::Result<_>>()?;
+
+            let corrodes_into = match &link.corrodes_into {
+                Some(name) => registry.resolve(name)?,
+                None => MaterialId::EMPTY,
+            };
+
+            let settles_into = match &link.settles_into {
+                Some(name) => Some(registry.resolve(name)?),
+                None => None,
+            };
+
+            let compaction = match &link.compaction {
+                Some((depth, into)) => Some(Compaction {
+                    depth: *depth,
+                    into: registry.resolve(into)?,
+                }),
+                None => None,
+            };
+
+            let absorbs = match &link.absorption {
+                Some((liquid, into)) => Some(Absorption {
+                    liquid: registry.resolve(liquid)?,
+                    into: registry.resolve(into)?,
+                }),
+                None => None,
+            };
+
+            let drying = match &link.drying {
+                Some((time, into)) => Some(Drying {
+                    time: *time,
+                    into: registry.resolve(into)?,
+                }),
+                None => None,
+            };
+
+            let dissolving = match &link.dissolving {
+                Some((solvent, into)) => {
+                    let dissolving = Dissolving {
+                        solvent: registry.resolve(solvent)?,
+                        into: registry.resolve(into)?,
+                    };
+
+                    if registry
+                        .get(dissolving.into)
+                        .is_none_or(|solution| solution.saturation == 0)
+                    {
+                        return Err(invalid_data(&format!(
+                            "{} dissolves into {} which has no saturation",
+                            registry.materials[index].name, into
+                        )));
+                    }
+
+                    Some(dissolving)
+                }
+                None => None,
+            };
+
+            let material = &mut registry.materials[index];
+            material.melting = melting?;
+            material.boiling = boiling?;
+            material.freezing = freezing?;
+            material.decays_into = decays_into;
+            material.corrodes_into = corrodes_into;
+            material.settles_into = settles_into;
+            material.compaction = compaction;
+            material.absorbs = absorbs;
+            material.drying = drying;
+            material.dissolving = dissolving;
+        }
+
+        // Anything that ignites sets fire to the flammable materials it
+        // touches, turning them into `burns_into` (Fire unless given)
+        let igniters: Vec<MaterialId> = registry
+            .iter()
+            .filter(|(_id, material)| material.ignites)
+            .map(|(id, _material)| id)
+            .collect();
+
+        for (index, link) in links.iter().enumerate() {
+            let flammability = registry.materials[index].flammability;
+
+            if flammability == 0.0 {
+                continue;
+            }
+
+            let fuel = MaterialId(index as u16 + 1);
+            let burns_into = registry.resolve(link.burns_into.as_deref().unwrap_or("Fire"))?;
+
+            for &igniter in &igniters {
+                registry.reactions.insert(
+                    (igniter, fuel),
+                    Reaction {
+                        products: (igniter, burns_into),
+                        chance: flammability,
+                    },
+                );
+            }
+        }
+
+        for entry in file.reaction {
+            if !(0.0..=1.0).contains(&entry.chance) {
+                return Err(invalid_data(&format!(
+                    "reaction chance {} is not between 0 and 1",
+                    entry.chance
+                )));
+            }
+
+            let [first, second] = &entry.reactants;
+            let first = registry.resolve(first)?;
+            let second = registry.resolve(second)?;
+
+            if first.is_empty() {
+                return Err(invalid_data("the first reactant can not be Empty"));
+            }
+
+            let [first_product, second_product] = &entry.products;
+            let products = (
+                registry.resolve(first_product)?,
+                registry.resolve(second_product)?,
+            );
+
+            registry.reactions.insert(
+                (first, second),
+                Reaction {
+                    products,
+                    chance: entry.chance,
+                },
+            );
+        }
+
+        Ok(registry)
+    }
+
+    /// Id of a material named in a reaction or transition, `Empty` is
+    /// allowed and stands for empty space.
+    fn resolve(&self, name: &str) -> io::Result<MaterialId> {
+        match name {
+            EMPTY_NAME => Ok(MaterialId::EMPTY),
+            name => self
+                .id(name)
+                .ok_or_else(|| invalid_data(&format!("unknown material {}", name))),
+        }
+    }
+
+    /// Definition of a material, `None` for empty space.
+    pub fn get(&self, id: MaterialId) -> Option<&Material> {
+        if id.is_empty() {
+            return None;
+        }
+
+        self.materials.get(id.0 as usize - 1)
+    }
+
+    pub fn reactions(&self) -> &ReactionTable {
+        &self.reactions
+    }
+
+    pub fn id(&self, name: &str) -> Option<MaterialId> {
+        self.iter()
+            .find(|(_id, material)| material.name == name)
+            .map(|(id, _material)| id)
+    }
+
+    /// Material selected by the given number key
+    pub fn by_key(&self, key: u8) -> Option<MaterialId> {
+        self.iter()
+            .find(|(_id, material)| material.key == Some(key))
+            .map(|(id, _material)| id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (MaterialId, &Material)> {
+        self.materials
+            .iter()
+            .enumerate()
+            .map(|(index, material)| (MaterialId(index as u16 + 1), material))
+    }
+
+    /// What a cell of the material leaves behind when its lifetime runs out
+    pub fn decays_into(&self, id: MaterialId, rng: &mut SimulationRng) -> MaterialId {
+        match self.get(id) {
+            Some(material) if !material.decays_into.is_empty() => {
+                material.decays_into[rng.gen_range(0..material.decays_into.len())]
+            }
+            _ => MaterialId::EMPTY,
+        }
+    }
+
+    /// What a cell destroyed by a blast leaves behind
+    pub fn debris_cell(&self, rng: &mut SimulationRng) -> Cell {
+        if self.explosion_debris.is_empty() {
+            return Cell::EMPTY;
+        }
+
+        let debris = self.explosion_debris[rng.gen_range(0..self.explosion_debris.len())];
+
+        self.create_cell(debris, rng).unwrap_or(Cell::EMPTY)
+    }
+
+    /// A fresh cell of the material with a random shade from its palette
+    pub fn create_cell(&self, id: MaterialId, rng: &mut SimulationRng) -> Option<Cell> {
+        let material = self.get(id)?;
+        let shade = rng.gen_range(0..material.colors.len()) as u8;
+
+        let mut cell = Cell::new(id, shade, material.temperature);
+
+        if let Some(lifetime) = material.lifetime {
+            cell.lifetime = rng.gen_range(lifetime / 2..=lifetime).max(1);
+        }
+        if material.drying.is_some() {
+            cell.moisture = u8::MAX;
+        }
+        cell.falling = material.phase != Phase::Solid;
+
+        Some(cell)
+    }
+}
+
+/// Parses colours written as `#RRGGBB`
+fn parse_color(color: &str) -> io::Result<Color> {
+    let hex = color
+        .strip_prefix('#')
+        .filter(|hex| hex.len() == 6 && hex.is_ascii())
+        .ok_or_else(|| invalid_data(&format!("invalid color {}", color)))?;
+
+    let channel = |index: usize| {
+        u8::from_str_radix(&hex[index..index + 2], 16)
+            .map_err(|_| invalid_data(&format!("invalid color {}", color)))
+    };
+
+    Ok(Color::from_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A definition file with the given entries added to a plain material
+    fn source(extra: &str) -> String {
+        format!(
+            r##"
+                [[material]]
+                name = "Sand"
+                key = 1
+                phase = "powder"
+                density = 1600.0
+                colors = ["#DEB26F"]
+                {}
+            "##,
+            extra
+        )
+    }
+
+    fn error(source: &str) -> String {
+        MaterialRegistry::from_toml(source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn bundled_materials_load() {
+        let registry =
+            MaterialRegistry::from_toml(include_str!("../../resources/materials.toml")).unwrap();
+
+        assert!(registry.id("Sand").is_some());
+        assert_eq!(registry.by_key(1), registry.id("Sand"));
+
+        // Gravel's angle of repose gives it a steeper slope than sand
+        let slope = |name| registry.get(registry.id(name).unwrap()).unwrap().slope;
+        assert!(slope("Gravel") > slope("Sand"));
+    }
+
+    #[test]
+    fn rejects_bad_colors() {
+        for color in ["DEB26F", "#DEB26", "#GGGGGG", "#aé€"] {
+            let source = source("").replace("#DEB26F", color);
+
+            assert_eq!(error(&source), format!("invalid color {}", color));
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_names_and_keys() {
+        let duplicate_name = source(
+            r##"
+                [[material]]
+                name = "Sand"
+                phase = "powder"
+                density = 1600.0
+                colors = ["#DEB26F"]
+            "##,
+        );
+        assert_eq!(error(&duplicate_name), "duplicate material Sand");
+
+        let duplicate_key = source(
+            r##"
+                [[material]]
+                name = "Stone"
+                key = 1
+                phase = "solid"
+                density = 2600.0
+                colors = ["#625F59"]
+            "##,
+        );
+        assert_eq!(error(&duplicate_key), "Stone key 1 is already taken");
+    }
+
+    #[test]
+    fn rejects_the_eraser_key() {
+        let source = source("").replace("key = 1", "key = 3");
+
+        assert_eq!(error(&source), "Sand key 3 is not a free number key");
+    }
+
+    #[test]
+    fn rejects_unknown_targets() {
+        let reaction = source(
+            r#"
+                [[reaction]]
+                reactants = ["Sand", "Water"]
+                products = ["Sand", "Sand"]
+                chance = 1.0
+            "#,
+        );
+        assert_eq!(error(&reaction), "unknown material Water");
+
+        let transition = source(
+            r#"
+                melting_point = 1700.0
+                melts_into = "Glass"
+            "#,
+        );
+        assert_eq!(error(&transition), "unknown material Glass");
+    }
+
+    #[test]
+    fn angle_of_repose_sets_a_fractional_slope() {
+        let slope = |angle: f32| {
+            let source = source(&format!("angle_of_repose = {:.1}", angle));
+            let registry = MaterialRegistry::from_toml(&source).unwrap();
+
+            registry.get(registry.id("Sand").unwrap()).unwrap().slope
+        };
+
+        assert_eq!(slope(45.0), 1.0);
+        assert!(slope(30.0) < slope(45.0));
+        assert!(slope(45.0) < slope(55.0));
+        assert!(slope(55.0) < slope(60.0));
+
+        for angle in [0.0, 90.0, -10.0, 120.0] {
+            let source = source(&format!("angle_of_repose = {:.1}", angle));
+
+            assert_eq!(
+                error(&source),
+                "Sand angle_of_repose is not between 0 and 90 degrees"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_dispersion_past_reach() {
+        let source = source("dispersion = 17");
+
+        assert_eq!(error(&source), "Sand dispersion is not between 0 and 16");
+    }
+}
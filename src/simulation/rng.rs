@@ -0,0 +1,16 @@
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Random number generator used by every part of the simulation.
+/// ChaCha produces the same stream on every platform, so a scene and a
+/// seed always replay identically.
+pub type SimulationRng = ChaCha8Rng;
+
+/// Separate stream for one chunk on one tick, so the result does not
+/// depend on which thread updated the chunk or in what order.
+pub fn chunk_rng(seed: u64, tick: u64, chunk: usize) -> SimulationRng {
+    let mut rng = SimulationRng::seed_from_u64(seed ^ tick.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    rng.set_stream(chunk as u64);
+
+    rng
+}
@@ -0,0 +1,170 @@
+use std::fs;
+use std::io::{self, Error, ErrorKind};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::components::{
+    bounds::Bounds, cell::Cell, grid_position::GridPosition, material::MaterialId,
+};
+
+use super::{grid::Grid, registry::MaterialRegistry, world::World};
+
+const HEADER: &str = "sand-simulation-scene 7";
+
+/// Writes the scene and its seed as plain text, one line per row with
+/// each cell stored as `material:shade:temperature:lifetime:health:moisture:concentration`.
+///
+/// Material ids depend on the order of the definition file, so the names
+/// in use are listed up front and cells are matched up by name on load.
+///
+/// Only what is needed to replay is saved, motion state such as
+/// `Cell::falling` starts fresh when the scene is loaded.
+pub fn save(world: &World, path: &Path) -> io::Result<()> {
+    let grid = world.grid();
+    let bounds = grid.bounds();
+
+    let mut contents = String::new();
+
+    contents.push_str(&format!("{}\n", HEADER));
+    contents.push_str(&format!("seed {}\n", world.seed()));
+    contents.push_str(&format!("size {} {}\n", bounds.width, bounds.height));
+
+    let names: Vec<&str> = world
+        .materials()
+        .iter()
+        .map(|(_id, material)| material.name.as_str())
+        .collect();
+
+    contents.push_str(&format!("materials {}\n", names.join(" ")));
+
+    for y in 0..bounds.height {
+        let row: Vec<String> = (0..bounds.width)
+            .map(|x| {
+                let cell = grid.get(GridPosition::new(x, y)).unwrap_or(Cell::EMPTY);
+
+                format!(
+                    "{}:{}:{}:{}:{}:{}:{}",
+                    cell.material.0,
+                    cell.shade,
+                    cell.temperature,
+                    cell.lifetime,
+                    cell.health,
+                    cell.moisture,
+                    cell.concentration
+                )
+            })
+            .collect();
+
+        contents.push_str(&row.join(" "));
+        contents.push('\n');
+    }
+
+    fs::write(path, contents)
+}
+
+pub fn load(path: &Path, materials: Arc<MaterialRegistry>) -> io::Result<World> {
+    let contents = fs::read_to_string(path)?;
+    let mut lines = contents.lines();
+
+    if lines.next() != Some(HEADER) {
+        return Err(invalid_data("missing scene header"));
+    }
+
+    let seed = parse_field(lines.next(), "seed")?
+        .parse::<u64>()
+        .map_err(|_| invalid_data("invalid seed"))?;
+
+    let size: Vec<i32> = parse_field(lines.next(), "size")?
+        .split_whitespace()
+        .map(|value| value.parse::<i32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid_data("invalid size"))?;
+
+    let bounds = match size[..] {
+        [width, height] if width >= 0 && height >= 0 => Bounds::new(width, height),
+        _ => return Err(invalid_data("invalid size")),
+    };
+
+    // Indexed by saved id - 1, `None` for materials no longer defined
+    let saved_materials: Vec<Option<MaterialId>> = parse_field(lines.next(), "materials")?
+        .split_whitespace()
+        .map(|name| materials.id(name))
+        .collect();
+
+    let mut grid = Grid::new(bounds);
+
+    for y in 0..bounds.height {
+        let row = lines.next().ok_or_else(|| invalid_data("missing row"))?;
+
+        for (x, token) in row.split_whitespace().enumerate() {
+            grid.set(
+                GridPosition::new(x as i32, y),
+                parse_cell(token, &saved_materials)?,
+            );
+        }
+    }
+
+    Ok(World::from_grid(grid, seed, materials))
+}
+
+fn parse_field<'a>(line: Option<&'a str>, name: &str) -> io::Result<&'a str> {
+    line.and_then(|line| line.strip_prefix(name))
+        .map(|value| value.trim())
+        .ok_or_else(|| invalid_data(&format!("missing {}", name)))
+}
+
+fn parse_cell(token: &str, saved_materials: &[Option<MaterialId>]) -> io::Result<Cell> {
+    let fields: Vec<&str> = token.split(':').collect();
+
+    let (material, shade, temperature, lifetime, health, moisture, concentration) = match fields[..]
+    {
+        [material, shade, temperature, lifetime, health, moisture, concentration] => (
+            material,
+            shade,
+            temperature,
+            lifetime,
+            health,
+            moisture,
+            concentration,
+        ),
+        _ => return Err(invalid_data("invalid cell")),
+    };
+
+    let material = match material.parse::<u16>() {
+        Ok(0) => MaterialId::EMPTY,
+        Ok(id) => saved_materials
+            .get(id as usize - 1)
+            .copied()
+            .flatten()
+            .ok_or_else(|| invalid_data("unknown material"))?,
+        Err(_) => return Err(invalid_data("invalid material")),
+    };
+
+    let shade = shade
+        .parse::<u8>()
+        .map_err(|_| invalid_data("invalid shade"))?;
+
+    let temperature = temperature
+        .parse::<f32>()
+        .map_err(|_| invalid_data("invalid temperature"))?;
+
+    let mut cell = Cell::new(material, shade, temperature);
+    cell.lifetime = lifetime
+        .parse::<u16>()
+        .map_err(|_| invalid_data("invalid lifetime"))?;
+    cell.health = health
+        .parse::<u8>()
+        .map_err(|_| invalid_data("invalid health"))?;
+    cell.moisture = moisture
+        .parse::<u8>()
+        .map_err(|_| invalid_data("invalid moisture"))?;
+    cell.concentration = concentration
+        .parse::<u8>()
+        .map_err(|_| invalid_data("invalid concentration"))?;
+
+    Ok(cell)
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
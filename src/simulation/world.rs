@@ -0,0 +1,1413 @@
+use std::sync::Arc;
+
+use crate::blocks::block::MAX_SPEED;
+use crate::components::{
+    block_types::BlockType,
+    bounds::Bounds,
+    cell::Cell,
+    directions::Direction,
+    grid_position::GridPosition,
+    material::{Explosive, Material, MaterialId},
+    phase::Phase,
+};
+
+use ggez::glam::Vec2;
+
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use super::{
+    chunk::{ChunkMap, DirtyRect, MAX_REACH, PHASES},
+    context::UpdateContext,
+    grid::Grid,
+    heat::{self, AMBIENT_TEMPERATURE},
+    registry::MaterialRegistry,
+    rng::{chunk_rng, SimulationRng},
+};
+
+/// How far past its radius a blast still flings loose cells, as a multiple
+/// of the radius
+const FLING_RANGE: f32 = 1.5;
+
+/// Chance each tick that a buried cell packs down into its compacted form
+const COMPACTION_CHANCE: f64 = 0.02;
+
+/// Chance each tick that a cell soaks up a neighbouring cell of the liquid
+/// it absorbs
+const ABSORPTION_CHANCE: f64 = 0.1;
+
+/// Chance each tick that a cell dissolves into a neighbouring cell of its
+/// solvent
+const DISSOLVING_CHANCE: f64 = 0.1;
+
+/// Degrees above ambient that add another ambient rate of drying, so a
+/// cell at 100 degrees dries nine times as fast
+const DRYING_WARMTH: f32 = 10.0;
+
+/// Owns every block and runs the simulation without needing a window,
+/// so it can be stepped from tests, benchmarks or the renderer alike.
+pub struct World {
+    grid: Grid,
+    chunks: ChunkMap,
+    /// Stamped onto cells as they update so each moves at most once per
+    /// tick. Starts at zero and goes up before every tick, so new cells
+    /// never look like they already moved.
+    tick_count: u64,
+    seed: u64,
+    rng: SimulationRng,
+    materials: Arc<MaterialRegistry>,
+}
+
+impl World {
+    pub fn new(bounds: Bounds, seed: u64, materials: Arc<MaterialRegistry>) -> Self {
+        Self::from_grid(Grid::new(bounds), seed, materials)
+    }
+
+    /// Starts a simulation from an existing scene, the same grid and seed
+    /// always produce the same ticks.
+    pub fn from_grid(grid: Grid, seed: u64, materials: Arc<MaterialRegistry>) -> Self {
+        let mut chunks = ChunkMap::new(grid.bounds());
+        chunks.wake_all();
+
+        Self {
+            grid,
+            chunks,
+            tick_count: 0,
+            seed,
+            rng: SimulationRng::seed_from_u64(seed),
+            materials,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn resize(&mut self, bounds: Bounds) {
+        self.grid = self.grid.resized(bounds);
+
+        self.chunks = ChunkMap::new(bounds);
+        self.chunks.wake_all();
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn chunks(&self) -> &ChunkMap {
+        &self.chunks
+    }
+
+    pub fn materials(&self) -> &Arc<MaterialRegistry> {
+        &self.materials
+    }
+
+    pub fn position_occupied(&self, position: GridPosition) -> bool {
+        !self.grid.is_empty(position)
+    }
+
+    pub fn spawn_block(&mut self, block_type: BlockType, position: GridPosition) {
+        if self.position_occupied(position) {
+            return;
+        }
+
+        let cell = match block_type
+            .material()
+            .and_then(|material| self.materials.create_cell(material, &mut self.rng))
+        {
+            Some(cell) => cell,
+            None => return,
+        };
+
+        self.grid.set(position, cell);
+        self.chunks.wake(position);
+    }
+
+    pub fn remove_block(&mut self, position: GridPosition) {
+        if !self.position_occupied(position) {
+            return;
+        }
+
+        self.grid.set(position, Cell::EMPTY);
+        self.chunks.wake(position);
+    }
+
+    /// Blows up everything within `radius` cells of the centre. Cells the
+    /// blast is stronger than are destroyed and replaced with debris,
+    /// explosives caught in it go off next tick and loose grains and
+    /// liquids just beyond are flung away from the centre, up as well as
+    /// sideways.
+    ///
+    /// The force falls off linearly from `power` at the centre to nothing
+    /// at the edge and is compared against each material's hardness.
+    pub fn explode(&mut self, center: GridPosition, radius: f32, power: f32) {
+        let materials = self.materials.clone();
+        let fling_radius = radius * FLING_RANGE;
+        let reach = fling_radius.ceil() as i32;
+
+        for y in -reach..=reach {
+            for x in -reach..=reach {
+                let offset = Vec2::new(x as f32, y as f32);
+                let distance = offset.length();
+                let position = center + GridPosition::new(x, y);
+
+                if distance > fling_radius {
+                    continue;
+                }
+
+                let mut cell = match self.grid.get(position) {
+                    Some(cell) => cell,
+                    None => continue,
+                };
+
+                let material = match materials.get(cell.material) {
+                    Some(material) => material,
+                    None => continue,
+                };
+
+                let force = power * (1.0 - distance / radius);
+
+                if let Some(explosive) = material.explosive {
+                    cell.temperature = cell.temperature.max(explosive.detonation_point + 1.0);
+                } else if force > material.hardness {
+                    cell = materials.debris_cell(&mut self.rng);
+                } else if matches!(material.phase, Phase::Powder | Phase::Liquid) && distance > 0.0
+                {
+                    let push = power * (1.0 - distance / fling_radius);
+
+                    cell.velocity = (cell.velocity + offset / distance * push)
+                        .clamp(Vec2::splat(-MAX_SPEED), Vec2::splat(MAX_SPEED));
+                    cell.falling = true;
+                } else {
+                    continue;
+                }
+
+                self.grid.set(position, cell);
+                self.chunks.wake(position);
+            }
+        }
+    }
+
+    /// Advances the simulation by a single step.
+    ///
+    /// Awake chunks are spread across threads in checkerboard phases.
+    /// Within a chunk rows are scanned bottom to top so grains land before
+    /// the ones above them move, and the column direction flips every tick
+    /// to avoid piles leaning to one side. Only dirty rectangles are visited.
+    pub fn tick(&mut self) {
+        self.tick_count += 1;
+        self.chunks.begin_tick();
+
+        let seed = self.seed;
+        let tick_count = self.tick_count;
+        let left_to_right = tick_count.is_multiple_of(2);
+        let materials = &*self.materials;
+        let mut detonations = Vec::new();
+
+        for phase in PHASES {
+            let tasks = self.chunks.awake_in_phase(phase);
+            let chunks = &self.chunks;
+            let grid = self.grid.shared();
+
+            let updates: Vec<(Vec<GridPosition>, Vec<GridPosition>, Vec<Detonation>)> = tasks
+                .par_iter()
+                .map(|&(index, rect)| {
+                    let mut update = ChunkUpdate {
+                        ctx: UpdateContext {
+                            grid: grid.limited_to(chunks.area(index)),
+                            materials,
+                            rng: chunk_rng(seed, tick_count, index),
+                            origin: rect.min,
+                        },
+                        clock: tick_count,
+                        woken: Vec::new(),
+                        flowed: Vec::new(),
+                        detonations: Vec::new(),
+                    };
+
+                    update.run(rect, left_to_right);
+                    (update.woken, update.flowed, update.detonations)
+                })
+                .collect();
+
+            for (woken, flowed, chunk_detonations) in updates {
+                for position in woken {
+                    self.chunks.wake(position);
+                }
+
+                for position in flowed {
+                    self.chunks.wake_row(position, MAX_REACH);
+                }
+
+                detonations.extend(chunk_detonations);
+            }
+        }
+
+        // Blasts reach well past a chunk so they wait for the serial part
+        for (position, explosive) in detonations {
+            self.explode(position, explosive.radius, explosive.power);
+        }
+    }
+}
+
+/// Work for a single chunk during one checkerboard phase.
+struct ChunkUpdate<'a> {
+    ctx: UpdateContext<'a>,
+    clock: u64,
+    /// Cells that changed, applied to the chunk map once the phase is done
+    woken: Vec<GridPosition>,
+    /// Cells liquids moved out of, waking the liquids that could see them
+    flowed: Vec<GridPosition>,
+    /// Explosives that went off, blown up once the tick is done
+    detonations: Vec<Detonation>,
+}
+
+type Detonation = (GridPosition, Explosive);
+
+impl ChunkUpdate<'_> {
+    fn run(&mut self, rect: DirtyRect, left_to_right: bool) {
+        for y in (rect.min.y..=rect.max.y).rev() {
+            for column in 0..=(rect.max.x - rect.min.x) {
+                let x = if left_to_right {
+                    rect.min.x + column
+                } else {
+                    rect.max.x - column
+                };
+
+                self.update_cell(GridPosition::new(x, y));
+            }
+        }
+    }
+
+    /// Updates the cell in place, moves are swaps so no cell is ever lost.
+    fn update_cell(&mut self, position: GridPosition) {
+        let mut cell = match self.ctx.grid.get(position) {
+            Some(cell) if cell.clock != self.clock => cell,
+            _ => return,
+        };
+
+        self.ctx.origin = position;
+
+        if self.react(position, cell) {
+            return;
+        }
+
+        let ctx = &mut self.ctx;
+        let materials = ctx.materials;
+
+        let material = match materials.get(cell.material) {
+            Some(material) => material,
+            None => return,
+        };
+
+        if let Some(explosive) = material.explosive {
+            let touching_fire = ctx.touching(position, |neighbour| neighbour.ignites);
+
+            if touching_fire || cell.temperature > explosive.detonation_point {
+                self.replace_cell(position, MaterialId::EMPTY, None);
+                self.detonations.push((position, explosive));
+                return;
+            }
+        }
+
+        let behaviour = material.behaviour();
+
+        cell.clock = self.clock;
+        let original = cell;
+
+        if material.corrosion > 0 && self.corrode(position, &mut cell, material) {
+            self.replace_cell(position, material.corrodes_into, None);
+            return;
+        }
+
+        heat::conduct(&mut cell, position, &self.ctx);
+
+        if let Some(into) = heat::transition(&cell, &self.ctx) {
+            self.replace_cell(position, into, Some(cell.temperature));
+            return;
+        }
+
+        let ctx = &mut self.ctx;
+
+        // Cells with air next to them draw liquids in and dry out, buried or
+        // sunken ones only soak without using up what they touch
+        let exposed = ctx
+            .neighbours(position)
+            .any(|(_, neighbour)| neighbour.is_empty());
+        let mut soaking = false;
+
+        if let Some(absorption) = material.absorbs.filter(|_absorption| !cell.falling) {
+            let liquid = ctx
+                .neighbours(position)
+                .find(|(_position, neighbour)| neighbour.material == absorption.liquid);
+
+            if let Some((liquid_position, _liquid)) = liquid {
+                soaking = true;
+
+                if cell.material == absorption.into {
+                    cell.moisture = u8::MAX;
+                } else {
+                    // Stays awake for as long as it touches the liquid
+                    self.woken.push(position);
+
+                    if ctx.rng.gen_bool(ABSORPTION_CHANCE) {
+                        self.replace_cell(position, absorption.into, Some(cell.temperature));
+
+                        if exposed {
+                            self.replace_cell(liquid_position, MaterialId::EMPTY, None);
+                        }
+
+                        return;
+                    }
+                }
+            }
+        }
+
+        if let Some(dissolving) = material.dissolving {
+            let solvent = ctx.neighbours(position).find(|(_, neighbour)| {
+                neighbour.material == dissolving.solvent
+                    || (neighbour.material == dissolving.into
+                        && materials
+                            .get(dissolving.into)
+                            .is_some_and(|solution| neighbour.concentration < solution.saturation))
+            });
+
+            if let Some((solvent_position, mut solvent)) = solvent {
+                // Stays awake for as long as there is room to dissolve into
+                self.woken.push(position);
+
+                if ctx.rng.gen_bool(DISSOLVING_CHANCE) {
+                    if solvent.material == dissolving.solvent {
+                        solvent = self.replace_cell(
+                            solvent_position,
+                            dissolving.into,
+                            Some(solvent.temperature),
+                        );
+                    }
+
+                    solvent.concentration += 1;
+                    self.ctx.grid.set(solvent_position, solvent);
+                    self.woken.push(solvent_position);
+                    self.replace_cell(position, MaterialId::EMPTY, None);
+                    return;
+                }
+            }
+        }
+
+        if let Some(drying) = material.drying.filter(|_drying| exposed && !soaking) {
+            // Dries out a little at a time, so stays awake until it has
+            self.woken.push(position);
+
+            let warmth = ((cell.temperature - AMBIENT_TEMPERATURE) / DRYING_WARMTH).max(0.0);
+            let chance = f32::from(u8::MAX) / f32::from(drying.time) * (1.0 + warmth);
+
+            if ctx.rng.gen::<f32>() < chance {
+                cell.moisture = cell.moisture.saturating_sub(1);
+            }
+
+            if cell.moisture == 0 {
+                self.replace_cell(position, drying.into, Some(cell.temperature));
+                return;
+            }
+        }
+
+        if cell.lifetime > 0 {
+            cell.lifetime -= 1;
+
+            if cell.lifetime == 0 {
+                let remains = materials.decays_into(cell.material, &mut ctx.rng);
+
+                self.replace_cell(position, remains, None);
+                return;
+            }
+        }
+
+        let next_position = behaviour.apply_gravity(&mut cell, position, ctx);
+        let next_position = behaviour.apply_motion(&mut cell, next_position, ctx);
+
+        if let Some(settles_into) = material.settles_into {
+            if next_position == position && !cell.falling {
+                self.replace_cell(position, settles_into, Some(cell.temperature));
+                return;
+            }
+        }
+
+        if let Some(compaction) = material.compaction {
+            let buried = (1..=compaction.depth.min(MAX_REACH)).all(|depth| {
+                ctx.grid
+                    .get(position + GridPosition::new(0, -depth))
+                    .is_some_and(|above| !above.is_empty())
+            });
+
+            if next_position == position && !cell.falling && buried {
+                // Packs down slowly, so stays awake until it does
+                self.woken.push(position);
+
+                if ctx.rng.gen_bool(COMPACTION_CHANCE) {
+                    self.replace_cell(position, compaction.into, Some(cell.temperature));
+                    return;
+                }
+            }
+        }
+
+        ctx.grid.set(position, cell);
+
+        if next_position != position && ctx.grid.get(next_position).is_some() {
+            ctx.grid.swap(position, next_position);
+            self.woken.push(position);
+            self.woken.push(next_position);
+
+            if material.phase == Phase::Liquid {
+                self.flowed.push(position);
+            }
+        } else if cell != original {
+            self.woken.push(position);
+        }
+    }
+
+    /// Puts a fresh cell of the material at the position, or empty space
+    /// for `MaterialId::EMPTY`, and wakes it. The cell starts at the given
+    /// temperature if there is one, otherwise at the material's own.
+    fn replace_cell(
+        &mut self,
+        position: GridPosition,
+        material: MaterialId,
+        temperature: Option<f32>,
+    ) -> Cell {
+        let ctx = &mut self.ctx;
+
+        let mut cell = ctx
+            .materials
+            .create_cell(material, &mut ctx.rng)
+            .unwrap_or(Cell::EMPTY);
+
+        if let Some(temperature) = temperature.filter(|_temperature| !cell.is_empty()) {
+            cell.temperature = temperature;
+        }
+
+        cell.clock = self.clock;
+        ctx.grid.set(position, cell);
+        self.woken.push(position);
+
+        cell
+    }
+
+    /// Eats into the neighbours an acid touches, wearing the acid down by
+    /// as much as it takes. Dissolved cells turn into `corrodes_into`.
+    /// Returns true once the acid is used up.
+    fn corrode(&mut self, position: GridPosition, cell: &mut Cell, material: &Material) -> bool {
+        let materials = self.ctx.materials;
+
+        for direction in [
+            Direction::Bottom,
+            Direction::Left,
+            Direction::Right,
+            Direction::Top,
+        ] {
+            let neighbour_position = position + direction.get_offset();
+
+            let mut neighbour = match self.ctx.grid.get(neighbour_position) {
+                Some(neighbour) if neighbour.material != cell.material => neighbour,
+                _ => continue,
+            };
+
+            let resistance = match materials.get(neighbour.material) {
+                Some(other) if !matches!(other.phase, Phase::Gas | Phase::Fire) => {
+                    other.corrosion_resistance
+                }
+                _ => continue,
+            };
+
+            if resistance >= 1.0 {
+                continue;
+            }
+
+            // Stays awake for as long as there is something left to eat
+            self.woken.push(position);
+
+            if self.ctx.rng.gen::<f32>() < resistance {
+                continue;
+            }
+
+            neighbour.health = neighbour.health.saturating_sub(material.corrosion);
+            cell.health = cell.health.saturating_sub(material.corrosion);
+
+            if neighbour.health == 0 {
+                self.replace_cell(neighbour_position, material.corrodes_into, None);
+            } else {
+                self.ctx.grid.set(neighbour_position, neighbour);
+                self.woken.push(neighbour_position);
+            }
+
+            if cell.health == 0 {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Rolls the reactions between the cell and each neighbour, replacing
+    /// both with the products of the first one that happens.
+    ///
+    /// Each touching pair is rolled once per tick, from the cell with the
+    /// lower material id or, for two of the same material, from the one
+    /// nearer the top left, so `chance` is the chance per tick.
+    fn react(&mut self, position: GridPosition, cell: Cell) -> bool {
+        let materials = self.ctx.materials;
+
+        if !materials.reactions().is_reactive(cell.material) {
+            return false;
+        }
+
+        for (neighbour_position, neighbour) in self.ctx.neighbours(position) {
+            let order =
+                |cell: Cell, position: GridPosition| (cell.material.0, position.y, position.x);
+
+            if order(neighbour, neighbour_position) < order(cell, position) {
+                continue;
+            }
+
+            for reaction in materials.reactions().get(cell.material, neighbour.material) {
+                // Stays awake for as long as the pair touches
+                self.woken.push(position);
+
+                if self.ctx.rng.gen::<f32>() >= reaction.chance {
+                    continue;
+                }
+
+                let (first, second) = reaction.products;
+
+                for (position, mut reactant, product) in [
+                    (position, cell, first),
+                    (neighbour_position, neighbour, second),
+                ] {
+                    // A reactant that comes out unchanged keeps its state
+                    if reactant.material == product {
+                        reactant.clock = self.clock;
+                        self.ctx.grid.set(position, reactant);
+                        self.woken.push(position);
+                    } else {
+                        self.replace_cell(position, product, None);
+                    }
+                }
+
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn materials() -> Arc<MaterialRegistry> {
+        let source = include_str!("../../resources/materials.toml");
+        Arc::new(MaterialRegistry::from_toml(source).unwrap())
+    }
+
+    fn block(materials: &MaterialRegistry, name: &str) -> BlockType {
+        BlockType::Material(materials.id(name).unwrap())
+    }
+
+    fn count(world: &World, name: &str) -> usize {
+        let id = world.materials().id(name).unwrap();
+
+        world
+            .grid()
+            .iter()
+            .filter(|(_, cell)| cell.material == id)
+            .count()
+    }
+
+    /// How many cells of the bottom row are filled
+    fn floor_width(world: &World) -> usize {
+        let bounds = world.grid().bounds();
+
+        (0..bounds.width)
+            .filter(|&x| {
+                !world
+                    .grid()
+                    .is_empty(GridPosition::new(x, bounds.height - 1))
+            })
+            .count()
+    }
+
+    /// How wide a column of the material spreads once dropped on the floor
+    fn pile_width(materials: &Arc<MaterialRegistry>, name: &str) -> usize {
+        let mut world = World::new(Bounds::new(64, 32), 0, materials.clone());
+
+        for y in 0..24 {
+            world.spawn_block(block(materials, name), GridPosition::new(32, y));
+        }
+
+        for _ in 0..300 {
+            world.tick();
+        }
+
+        floor_width(&world)
+    }
+
+    #[test]
+    fn tick_conserves_particles() {
+        let mut world = World::new(Bounds::new(32, 32), 0, materials());
+
+        for x in 4..28 {
+            for y in 0..12 {
+                world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(x, y));
+            }
+        }
+
+        for x in 10..20 {
+            world.spawn_block(block(world.materials(), "Stone"), GridPosition::new(x, 20));
+        }
+
+        let count = world.grid().iter().count();
+
+        for _ in 0..200 {
+            world.tick();
+            assert_eq!(world.grid().iter().count(), count);
+        }
+    }
+
+    #[test]
+    fn same_seed_replays_identically() {
+        let run = |seed| {
+            let mut world = World::new(Bounds::new(24, 24), seed, materials());
+
+            for x in 8..16 {
+                for y in 0..8 {
+                    world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(x, y));
+                }
+            }
+
+            for _ in 0..100 {
+                world.tick();
+            }
+
+            world.grid().clone()
+        };
+
+        assert_eq!(run(42), run(42));
+    }
+
+    #[test]
+    fn settled_scene_sleeps() {
+        let mut world = World::new(Bounds::new(64, 64), 0, materials());
+
+        for x in 24..40 {
+            for y in 0..16 {
+                world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(x, y));
+            }
+        }
+
+        for _ in 0..400 {
+            world.tick();
+        }
+
+        assert_eq!(world.chunks().awake_count(), 0);
+    }
+
+    #[test]
+    fn long_sleeping_cells_wake_up() {
+        // Woken around multiples of 256 ticks after the sand last moved
+        for ticks in [255, 256, 257, 511, 512, 513] {
+            let mut world = World::new(Bounds::new(8, 8), 0, materials());
+
+            for x in 0..8 {
+                world.spawn_block(block(world.materials(), "Stone"), GridPosition::new(x, 5));
+            }
+
+            world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(4, 4));
+
+            for _ in 0..ticks {
+                world.tick();
+            }
+
+            world.remove_block(GridPosition::new(4, 5));
+
+            for _ in 0..50 {
+                world.tick();
+            }
+
+            assert!(world.grid().is_empty(GridPosition::new(4, 4)));
+            assert_eq!(count(&world, "Sand"), 1);
+        }
+    }
+
+    #[test]
+    fn new_cells_move_on_their_first_tick() {
+        let mut world = World::new(Bounds::new(8, 8), 0, materials());
+
+        // Spawned just before the tick count passes a multiple of 256
+        for _ in 0..255 {
+            world.tick();
+        }
+
+        world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(4, 0));
+
+        for _ in 0..50 {
+            world.tick();
+        }
+
+        assert_eq!(floor_width(&world), 1);
+        assert_eq!(count(&world, "Sand"), 1);
+    }
+
+    #[test]
+    fn sand_sinks_through_water() {
+        let mut world = World::new(Bounds::new(1, 8), 0, materials());
+
+        for y in 4..8 {
+            world.spawn_block(block(world.materials(), "Water"), GridPosition::new(0, y));
+        }
+
+        world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(0, 0));
+
+        for _ in 0..20 {
+            world.tick();
+        }
+
+        // Sunken sand soaks without using up any water
+        let sand = [
+            world.materials().id("Sand").unwrap(),
+            world.materials().id("WetSand").unwrap(),
+        ];
+        let bottom = world.grid().get(GridPosition::new(0, 7)).unwrap();
+        assert!(sand.contains(&bottom.material));
+        assert_eq!(world.grid().iter().count(), 5);
+    }
+
+    #[test]
+    fn touching_materials_react() {
+        let source = format!(
+            "{}\n{}",
+            include_str!("../../resources/materials.toml"),
+            r#"
+                [[reaction]]
+                reactants = ["Sand", "Water"]
+                products = ["Stone", "Empty"]
+                chance = 1.0
+            "#
+        );
+        let materials = Arc::new(MaterialRegistry::from_toml(&source).unwrap());
+        let stone = materials.id("Stone").unwrap();
+
+        // Either order of the reactants gives the same result
+        for (sand_x, water_x) in [(0, 1), (1, 0)] {
+            let mut world = World::new(Bounds::new(2, 1), 0, materials.clone());
+
+            world.spawn_block(block(&materials, "Sand"), GridPosition::new(sand_x, 0));
+            world.spawn_block(block(&materials, "Water"), GridPosition::new(water_x, 0));
+            world.tick();
+
+            let sand = world.grid().get(GridPosition::new(sand_x, 0)).unwrap();
+            assert_eq!(sand.material, stone);
+            assert!(world.grid().is_empty(GridPosition::new(water_x, 0)));
+        }
+    }
+
+    #[test]
+    fn ice_melts_in_warm_water() {
+        let mut world = World::new(Bounds::new(5, 1), 0, materials());
+
+        for x in 0..5 {
+            let name = if x == 2 { "Ice" } else { "Water" };
+            world.spawn_block(block(world.materials(), name), GridPosition::new(x, 0));
+        }
+
+        for _ in 0..200 {
+            world.tick();
+        }
+
+        let water = world.materials().id("Water").unwrap();
+        assert!(world.grid().iter().all(|(_, cell)| cell.material == water));
+        assert_eq!(world.grid().iter().count(), 5);
+    }
+
+    #[test]
+    fn fire_burns_through_wood() {
+        let mut world = World::new(Bounds::new(16, 8), 0, materials());
+
+        // Lit from inside so the flame can not drift away before catching
+        world.spawn_block(block(world.materials(), "Fire"), GridPosition::new(0, 7));
+
+        for x in 0..16 {
+            for y in 5..8 {
+                world.spawn_block(block(world.materials(), "Wood"), GridPosition::new(x, y));
+            }
+        }
+
+        for _ in 0..2000 {
+            world.tick();
+        }
+
+        assert_eq!(count(&world, "Wood"), 0);
+        assert_eq!(count(&world, "Fire"), 0);
+    }
+
+    #[test]
+    fn steam_rises_above_water() {
+        let mut world = World::new(Bounds::new(1, 8), 0, materials());
+
+        for y in 0..4 {
+            world.spawn_block(block(world.materials(), "Water"), GridPosition::new(0, y));
+        }
+
+        world.spawn_block(block(world.materials(), "Steam"), GridPosition::new(0, 7));
+
+        for _ in 0..20 {
+            world.tick();
+        }
+
+        let top = world.grid().get(GridPosition::new(0, 0)).unwrap();
+        assert_eq!(top.material, world.materials().id("Steam").unwrap());
+    }
+
+    #[test]
+    fn lava_hardens_in_water() {
+        let mut world = World::new(Bounds::new(1, 8), 0, materials());
+
+        for y in 4..8 {
+            world.spawn_block(block(world.materials(), "Water"), GridPosition::new(0, y));
+        }
+
+        world.spawn_block(block(world.materials(), "Lava"), GridPosition::new(0, 0));
+
+        for _ in 0..50 {
+            world.tick();
+        }
+
+        let lava = world.materials().id("Lava").unwrap();
+        let stone = world.materials().id("Stone").unwrap();
+
+        assert!(world.grid().iter().all(|(_, cell)| cell.material != lava));
+        assert!(world.grid().iter().any(|(_, cell)| cell.material == stone));
+    }
+
+    #[test]
+    fn stone_resists_small_blasts() {
+        let mut world = World::new(Bounds::new(16, 16), 0, materials());
+
+        for x in 4..12 {
+            world.spawn_block(block(world.materials(), "Stone"), GridPosition::new(x, 8));
+        }
+
+        world.explode(GridPosition::new(8, 8), 3.0, 5.0);
+        assert_eq!(count(&world, "Stone"), 8);
+
+        world.explode(GridPosition::new(8, 8), 3.0, 20.0);
+        assert!(count(&world, "Stone") < 8);
+    }
+
+    #[test]
+    fn blasts_fling_cells_above_upwards() {
+        let mut world = World::new(Bounds::new(16, 16), 0, materials());
+        let start = GridPosition::new(8, 4);
+
+        world.spawn_block(block(world.materials(), "Sand"), start);
+        world.explode(GridPosition::new(8, 8), 4.0, 10.0);
+
+        let cell = world.grid().get(start).unwrap();
+        assert_eq!(cell.velocity.x, 0.0);
+        assert!(cell.velocity.y < 0.0);
+
+        world.tick();
+
+        let (position, _) = world.grid().iter().next().unwrap();
+        assert_eq!(position.x, start.x);
+        assert!(position.y < start.y);
+    }
+
+    #[test]
+    fn gunpowder_detonates_next_to_fire() {
+        let mut world = World::new(Bounds::new(16, 8), 0, materials());
+
+        for x in 4..12 {
+            world.spawn_block(
+                block(world.materials(), "Gunpowder"),
+                GridPosition::new(x, 7),
+            );
+        }
+
+        world.spawn_block(block(world.materials(), "Fire"), GridPosition::new(3, 7));
+
+        for _ in 0..50 {
+            world.tick();
+        }
+
+        let gunpowder = world.materials().id("Gunpowder").unwrap();
+        assert!(world
+            .grid()
+            .iter()
+            .all(|(_, cell)| cell.material != gunpowder));
+    }
+
+    #[test]
+    fn acid_eats_sand_but_not_glass() {
+        let mut world = World::new(Bounds::new(1, 8), 0, materials());
+
+        world.spawn_block(block(world.materials(), "Glass"), GridPosition::new(0, 7));
+        world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(0, 6));
+        world.spawn_block(block(world.materials(), "Acid"), GridPosition::new(0, 5));
+
+        for _ in 0..200 {
+            world.tick();
+        }
+
+        assert_eq!(count(&world, "Sand"), 0);
+        assert_eq!(count(&world, "Acid"), 0);
+        assert_eq!(count(&world, "Glass"), 1);
+    }
+
+    #[test]
+    fn seed_sprouts_and_grows_into_water() {
+        let mut world = World::new(Bounds::new(8, 8), 0, materials());
+
+        for x in 1..8 {
+            world.spawn_block(block(world.materials(), "Water"), GridPosition::new(x, 7));
+        }
+
+        world.spawn_block(block(world.materials(), "Seed"), GridPosition::new(0, 0));
+
+        for _ in 0..400 {
+            world.tick();
+        }
+
+        assert_eq!(count(&world, "Seed"), 0);
+        assert!(count(&world, "Plant") > 1);
+        assert_eq!(count(&world, "Plant") + count(&world, "Water"), 8);
+    }
+
+    #[test]
+    fn water_levels_out() {
+        for (width, volume) in [(1, 16), (2, 16), (2, 20), (3, 24), (4, 40)] {
+            let mut world = World::new(Bounds::new(16, 16), 0, materials());
+
+            for index in 0..volume {
+                let position = GridPosition::new(4 + index % width, index / width);
+                world.spawn_block(block(world.materials(), "Water"), position);
+            }
+
+            for _ in 0..1000 {
+                world.tick();
+            }
+
+            // Every column ends up within a cell of the same depth
+            let depths: Vec<usize> = (0..16)
+                .map(|x| {
+                    (0..16)
+                        .filter(|&y| !world.grid().is_empty(GridPosition::new(x, y)))
+                        .count()
+                })
+                .collect();
+            let shallowest = depths.iter().min().unwrap();
+            let deepest = depths.iter().max().unwrap();
+
+            assert!(deepest - shallowest <= 1, "{} cells {:?}", volume, depths);
+            assert_eq!(count(&world, "Water"), volume as usize);
+            assert_eq!(world.chunks().awake_count(), 0, "{} cells", volume);
+        }
+    }
+
+    #[test]
+    fn liquids_settle_into_layers_by_density() {
+        let mut world = World::new(Bounds::new(8, 12), 0, materials());
+
+        // Stacked in the wrong order, lightest at the bottom
+        for (name, rows) in [("Sand", 0..4), ("Water", 4..8), ("Oil", 8..12)] {
+            for y in rows {
+                for x in 0..8 {
+                    world.spawn_block(block(world.materials(), name), GridPosition::new(x, y));
+                }
+            }
+        }
+
+        for _ in 0..1000 {
+            world.tick();
+        }
+
+        // Sand on the bottom may have soaked up some water on the way down
+        for (names, rows) in [
+            (&["Oil"][..], 0..4),
+            (&["Water"][..], 4..8),
+            (&["Sand", "WetSand"][..], 8..12),
+        ] {
+            let ids: Vec<MaterialId> = names
+                .iter()
+                .map(|name| world.materials().id(name).unwrap())
+                .collect();
+
+            for y in rows {
+                for x in 0..8 {
+                    let cell = world.grid().get(GridPosition::new(x, y)).unwrap();
+                    assert!(
+                        ids.contains(&cell.material),
+                        "{:?} expected at {}, {}",
+                        names,
+                        x,
+                        y
+                    );
+                }
+            }
+        }
+
+        assert_eq!(world.chunks().awake_count(), 0);
+    }
+
+    #[test]
+    fn snow_piles_steeper_than_sand() {
+        let materials = materials();
+
+        assert!(pile_width(&materials, "Snow") < pile_width(&materials, "Sand"));
+    }
+
+    #[test]
+    fn buried_snow_packs_into_ice() {
+        let mut world = World::new(Bounds::new(1, 16), 0, materials());
+
+        for y in 0..16 {
+            world.spawn_block(block(world.materials(), "Snow"), GridPosition::new(0, y));
+        }
+
+        for _ in 0..400 {
+            world.tick();
+        }
+
+        let ice = world.materials().id("Ice").unwrap();
+        let bottom = world.grid().get(GridPosition::new(0, 15)).unwrap();
+        let top = world.grid().get(GridPosition::new(0, 0)).unwrap();
+
+        assert_eq!(bottom.material, ice);
+        assert_ne!(top.material, ice);
+    }
+
+    #[test]
+    fn friction_holds_steeper_piles() {
+        let materials = Arc::new(
+            MaterialRegistry::from_toml(
+                r##"
+                    [[material]]
+                    name = "Loose"
+                    phase = "powder"
+                    density = 1600.0
+                    colors = ["#FFFFFF"]
+
+                    [[material]]
+                    name = "Grippy"
+                    phase = "powder"
+                    density = 1600.0
+                    friction = 0.8
+                    colors = ["#FFFFFF"]
+                "##,
+            )
+            .unwrap(),
+        );
+
+        assert!(pile_width(&materials, "Grippy") < pile_width(&materials, "Loose"));
+    }
+
+    #[test]
+    fn sand_soaks_up_water_and_dries_out() {
+        let mut world = World::new(Bounds::new(4, 2), 0, materials());
+
+        for x in 0..3 {
+            world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(x, 1));
+        }
+
+        world.spawn_block(block(world.materials(), "Water"), GridPosition::new(3, 1));
+
+        for _ in 0..200 {
+            world.tick();
+        }
+
+        assert_eq!(count(&world, "Water"), 0);
+        assert_eq!(count(&world, "WetSand"), 1);
+        assert_eq!(count(&world, "Sand"), 2);
+
+        for _ in 0..5000 {
+            world.tick();
+        }
+
+        assert_eq!(count(&world, "Sand"), 3);
+        assert_eq!(world.chunks().awake_count(), 0);
+    }
+
+    #[test]
+    fn wet_sand_holds_steeper_walls() {
+        let materials = materials();
+
+        assert!(pile_width(&materials, "WetSand") < pile_width(&materials, "Snow"));
+    }
+
+    #[test]
+    fn salt_dissolves_until_water_is_saturated() {
+        let mut world = World::new(Bounds::new(1, 8), 0, materials());
+
+        world.spawn_block(block(world.materials(), "Water"), GridPosition::new(0, 7));
+
+        for y in 0..7 {
+            world.spawn_block(block(world.materials(), "Salt"), GridPosition::new(0, y));
+        }
+
+        for _ in 0..500 {
+            world.tick();
+        }
+
+        let salt = world.materials().id("Salt").unwrap();
+        let salt_water = world.materials().id("SaltWater").unwrap();
+        let saturation = world.materials().get(salt_water).unwrap().saturation;
+
+        let solutions: Vec<&Cell> = world
+            .grid()
+            .iter()
+            .map(|(_, cell)| cell)
+            .filter(|cell| cell.material == salt_water)
+            .collect();
+
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(solutions[0].concentration, saturation);
+
+        // The salt left over sinks beneath the saturated water
+        let bottom = world.grid().get(GridPosition::new(0, 7)).unwrap();
+        assert_eq!(bottom.material, salt);
+        assert_eq!(world.grid().iter().count(), 8 - saturation as usize);
+        assert_eq!(world.chunks().awake_count(), 0);
+    }
+
+    #[test]
+    fn fresh_water_floats_on_salt_water() {
+        let mut world = World::new(Bounds::new(1, 8), 0, materials());
+
+        for y in 0..4 {
+            world.spawn_block(
+                block(world.materials(), "SaltWater"),
+                GridPosition::new(0, y),
+            );
+            world.spawn_block(
+                block(world.materials(), "Water"),
+                GridPosition::new(0, y + 4),
+            );
+        }
+
+        for _ in 0..200 {
+            world.tick();
+        }
+
+        for (name, rows) in [("Water", 0..4), ("SaltWater", 4..8)] {
+            let id = world.materials().id(name).unwrap();
+
+            for y in rows {
+                let cell = world.grid().get(GridPosition::new(0, y)).unwrap();
+                assert_eq!(cell.material, id, "{} expected at {}", name, y);
+            }
+        }
+    }
+
+    #[test]
+    fn flung_liquids_stay_within_reach() {
+        let materials = Arc::new(
+            MaterialRegistry::from_toml(
+                r##"
+                    [[material]]
+                    name = "Runny"
+                    phase = "liquid"
+                    density = 1000.0
+                    dispersion = 16
+                    colors = ["#FFFFFF"]
+                "##,
+            )
+            .unwrap(),
+        );
+        let mut world = World::new(Bounds::new(128, 32), 0, materials.clone());
+
+        // Next to the edge of a chunk, so the blast throws cells out of it
+        for x in 90..95 {
+            world.spawn_block(block(&materials, "Runny"), GridPosition::new(x, 31));
+        }
+
+        // Cells thrown at full speed still have to stop flowing within
+        // `MAX_REACH`, which debug builds check on every grid access
+        world.explode(GridPosition::new(92, 31), 2.0, 40.0);
+
+        for _ in 0..50 {
+            world.tick();
+        }
+    }
+
+    #[test]
+    fn fast_cells_only_swap_with_fluids_next_to_them() {
+        let mut world = World::new(Bounds::new(1, 64), 0, materials());
+
+        for y in 56..64 {
+            world.spawn_block(block(world.materials(), "Water"), GridPosition::new(0, y));
+        }
+
+        world.spawn_block(block(world.materials(), "Gravel"), GridPosition::new(0, 0));
+
+        // The gravel hits the water at full speed, the water it pushes aside
+        // may only rise by the one cell the gravel takes up
+        for _ in 0..100 {
+            world.tick();
+
+            for y in 0..55 {
+                let cell = world.grid().get(GridPosition::new(0, y)).unwrap();
+                assert_ne!(cell.material, world.materials().id("Water").unwrap());
+            }
+        }
+
+        let bottom = world.grid().get(GridPosition::new(0, 63)).unwrap();
+        assert_eq!(bottom.material, world.materials().id("Gravel").unwrap());
+    }
+
+    #[test]
+    fn velocity_carries_cells_sideways() {
+        let mut world = World::new(Bounds::new(32, 1), 0, materials());
+        let start = GridPosition::new(0, 0);
+
+        world.spawn_block(block(world.materials(), "Sand"), start);
+
+        let mut cell = world.grid().get(start).unwrap();
+        cell.velocity.x = 4.0;
+        world.grid.set(start, cell);
+
+        // Several cells in one tick, then slowing down until it rests
+        world.tick();
+        assert!(world.position_occupied(GridPosition::new(4, 0)));
+
+        for _ in 0..20 {
+            world.tick();
+        }
+
+        let (position, cell) = world.grid().iter().next().unwrap();
+        assert!(position.x > 4);
+        assert_eq!(cell.velocity, Vec2::ZERO);
+        assert_eq!(world.chunks().awake_count(), 0);
+    }
+
+    #[test]
+    fn momentum_passes_sideways_on_impact() {
+        let landing = |height| {
+            let mut world = World::new(Bounds::new(64, height), 0, materials());
+
+            world.spawn_block(block(world.materials(), "Sand"), GridPosition::new(32, 0));
+
+            for _ in 0..100 {
+                world.tick();
+            }
+
+            let (position, _) = world.grid().iter().next().unwrap();
+            (position.x - 32).abs()
+        };
+
+        // A short drop lands in place, a long one scatters the grain
+        assert_eq!(landing(2), 0);
+        assert!(landing(64) > 1);
+    }
+
+    #[test]
+    fn shallow_powders_spread_wider() {
+        let materials = Arc::new(
+            MaterialRegistry::from_toml(
+                r##"
+                    [[material]]
+                    name = "Fine"
+                    phase = "powder"
+                    density = 1600.0
+                    angle_of_repose = 30.0
+                    colors = ["#FFFFFF"]
+
+                    [[material]]
+                    name = "Coarse"
+                    phase = "powder"
+                    density = 1600.0
+                    colors = ["#FFFFFF"]
+                "##,
+            )
+            .unwrap(),
+        );
+
+        let spread = |name| {
+            let mut world = World::new(Bounds::new(64, 32), 0, materials.clone());
+
+            // Stacked on the floor so nothing lands hard enough to scatter
+            for y in 8..32 {
+                for x in 30..35 {
+                    world.spawn_block(block(&materials, name), GridPosition::new(x, y));
+                }
+            }
+
+            for _ in 0..500 {
+                world.tick();
+            }
+
+            assert_eq!(world.chunks().awake_count(), 0);
+            floor_width(&world)
+        };
+
+        assert!(spread("Fine") > spread("Coarse"));
+    }
+
+    #[test]
+    fn reactions_happen_at_their_chance_per_tick() {
+        let materials = Arc::new(
+            MaterialRegistry::from_toml(
+                r##"
+                    [[material]]
+                    name = "Left"
+                    phase = "solid"
+                    density = 1000.0
+                    colors = ["#FFFFFF"]
+
+                    [[material]]
+                    name = "Right"
+                    phase = "solid"
+                    density = 1000.0
+                    colors = ["#FFFFFF"]
+
+                    [[material]]
+                    name = "Reacted"
+                    phase = "solid"
+                    density = 1000.0
+                    colors = ["#FFFFFF"]
+
+                    [[reaction]]
+                    reactants = ["Left", "Right"]
+                    products = ["Reacted", "Reacted"]
+                    chance = 0.1
+                "##,
+            )
+            .unwrap(),
+        );
+        let reacted = materials.id("Reacted").unwrap();
+
+        let mut pairs = 0;
+        let mut reactions = 0;
+
+        for seed in 0..20 {
+            let mut world = World::new(Bounds::new(300, 1), seed, materials.clone());
+
+            // Pairs kept a cell apart so each only touches its partner
+            for x in (0..300).step_by(3) {
+                world.spawn_block(block(&materials, "Left"), GridPosition::new(x, 0));
+                world.spawn_block(block(&materials, "Right"), GridPosition::new(x + 1, 0));
+                pairs += 1;
+            }
+
+            world.tick();
+
+            reactions += world
+                .grid()
+                .iter()
+                .filter(|(_, cell)| cell.material == reacted)
+                .count()
+                / 2;
+        }
+
+        let rate = reactions as f32 / pairs as f32;
+        assert!((0.07..0.13).contains(&rate), "reacted at {}", rate);
+    }
+}